@@ -0,0 +1,602 @@
+// The moves players make.
+//
+// Every decision a player makes is an Action. UIs, bots and remote players all make moves through
+// GameState::apply and ask GameState::legal_actions what they may do, so the rules are only
+// written once, in the checks of GameState. Trades are listed too. An offer can only ask for cards
+// someone else has, and a counteroffer for cards the player offering has, so there are only so
+// many of them.
+
+use std::collections::HashSet;
+
+use devcard::DevCard;
+use error::GameError;
+use game::{GameState, Phase};
+use hand::Hand;
+use history::Record;
+use piece::PlayerId;
+use production::ProductionReport;
+use terrain::{Resource, RESOURCES};
+use trade::{count_lots, Offer, Response};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    // Place a settlement on the intersection, during the setup or by building it.
+    PlaceSettlement(usize),
+    // Place a road on the edge, during the setup or by building it.
+    PlaceRoad(usize),
+    // Upgrade the settlement on the intersection to a city.
+    BuildCity(usize),
+    Roll,
+    // Discard the cards after a 7. This is the one action players take out of turn.
+    Discard(Hand),
+    // Move the robber to the hex and steal from the victim, if anyone there has cards.
+    MoveRobber { row: usize, column: usize, victim: Option<PlayerId> },
+    BuyDevCard,
+    PlayKnight,
+    PlayRoadBuilding,
+    // Take the two cards from the bank.
+    PlayYearOfPlenty(Resource, Resource),
+    PlayMonopoly(Resource),
+    // Offer a trade to the other players, and withdraw it.
+    OfferTrade(Offer),
+    CancelTrade,
+    // Respond to the offer. The counteroffer is written from the side of the offering player.
+    AcceptTrade,
+    RejectTrade,
+    CounterTrade(Offer),
+    // Trade with the player, who accepted or countered the offer.
+    ConfirmTrade(PlayerId),
+    // Trade with the bank.
+    BankTrade(Offer),
+    EndTrade,
+    EndTurn,
+}
+
+// What came of an action, for the things the player couldn't know beforehand.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Outcome {
+    Done,
+    // The roll and what it produced, which is nothing on a 7.
+    Rolled(u8, Option<ProductionReport>),
+    // The card the robber stole.
+    Stole(Resource),
+    // The development card bought.
+    Bought(DevCard),
+    // The number of cards monopoly took.
+    Took(u32),
+}
+
+impl GameState {
+    // Check that the player can take the action now, without taking it.
+    pub fn check(&self, player: PlayerId, action: &Action) -> Result<(), GameError> {
+        match *action {
+            Action::PlaceSettlement(intersection) => self.check_settlement(player, intersection),
+            Action::PlaceRoad(edge) => self.check_road(player, edge),
+            Action::BuildCity(intersection) => self.check_city(player, intersection),
+            Action::Roll => self.check_roll(player),
+            Action::Discard(ref cards) => self.check_discard(player, cards),
+            Action::MoveRobber { row, column, victim } =>
+                self.check_robber(player, row, column, victim),
+            Action::BuyDevCard => self.check_buy_dev_card(player),
+            Action::PlayKnight => self.check_knight(player),
+            Action::PlayRoadBuilding => self.check_road_building(player),
+            Action::PlayYearOfPlenty(first, second) =>
+                self.check_year_of_plenty(player, first, second),
+            Action::PlayMonopoly(_) => self.check_monopoly(player),
+            Action::OfferTrade(ref offer) => self.check_offer_trade(player, offer),
+            Action::CancelTrade => self.check_cancel_trade(player),
+            Action::AcceptTrade => self.check_respond_trade(player, &Response::Accepted),
+            Action::RejectTrade => self.check_respond_trade(player, &Response::Rejected),
+            Action::CounterTrade(offer) =>
+                self.check_respond_trade(player, &Response::Countered(offer)),
+            Action::ConfirmTrade(partner) => self.check_confirm_trade(player, partner),
+            Action::BankTrade(ref offer) => self.check_bank_trade(player, offer),
+            Action::EndTrade => self.check_end_trade(player),
+            Action::EndTurn => self.check_end_turn(player),
+        }
+    }
+
+    // Take the action for the player. Nothing changes if the action is refused.
+    // The action is recorded in the history so it can be undone.
+    pub fn apply(&mut self, player: PlayerId, action: &Action) -> Result<Outcome, GameError> {
+        self.check(player, action)?;
+        let revert = self.get_revert(player, action);
+        let outcome = self.perform(player, action)?;
+        let record = Record {
+            player,
+            action: *action,
+            outcome: outcome.clone(),
+        };
+        self.record(record, revert);
+        Ok(outcome)
+    }
+
+    // Take the action without recording it, which redo does. Nothing changes if it's refused.
+    pub fn perform(&mut self, player: PlayerId, action: &Action) -> Result<Outcome, GameError> {
+        match *action {
+            Action::PlaceSettlement(intersection) => self.place_settlement(player, intersection)?,
+            Action::PlaceRoad(edge) => self.place_road(player, edge)?,
+            Action::BuildCity(intersection) => self.build_city(player, intersection)?,
+            Action::Roll => {
+                return self.roll(player).map(|(roll, production)| {
+                    Outcome::Rolled(roll, production)
+                });
+            }
+            Action::Discard(ref cards) => self.discard(player, cards)?,
+            Action::MoveRobber { row, column, victim } => {
+                if let Some(card) = self.move_robber(player, row, column, victim)? {
+                    return Ok(Outcome::Stole(card));
+                }
+            }
+            Action::BuyDevCard => return self.buy_dev_card(player).map(Outcome::Bought),
+            Action::PlayKnight => self.play_knight(player)?,
+            Action::PlayRoadBuilding => self.play_road_building(player)?,
+            Action::PlayYearOfPlenty(first, second) =>
+                self.play_year_of_plenty(player, first, second)?,
+            Action::PlayMonopoly(resource) =>
+                return self.play_monopoly(player, resource).map(Outcome::Took),
+            Action::OfferTrade(ref offer) => self.offer_trade(player, offer)?,
+            Action::CancelTrade => self.cancel_trade(player)?,
+            Action::AcceptTrade => self.respond_trade(player, &Response::Accepted)?,
+            Action::RejectTrade => self.respond_trade(player, &Response::Rejected)?,
+            Action::CounterTrade(offer) =>
+                self.respond_trade(player, &Response::Countered(offer))?,
+            Action::ConfirmTrade(partner) => self.confirm_trade(player, partner)?,
+            Action::BankTrade(ref offer) => self.bank_trade(player, offer)?,
+            Action::EndTrade => self.end_trade(player)?,
+            Action::EndTurn => self.end_turn(player)?,
+        }
+        Ok(Outcome::Done)
+    }
+
+    // Every action that would be accepted now, with the player who can take it.
+    pub fn legal_actions(&self) -> Vec<(PlayerId, Action)> {
+        let board = self.get_board();
+        let mut actions = vec![
+            Action::Roll,
+            Action::BuyDevCard,
+            Action::PlayKnight,
+            Action::PlayRoadBuilding,
+            Action::CancelTrade,
+            Action::EndTrade,
+            Action::EndTurn,
+        ];
+        for other in 0..self.get_players_size() {
+            actions.push(Action::ConfirmTrade(PlayerId(other)));
+        }
+        for (i, &first) in RESOURCES.iter().enumerate() {
+            for &second in &RESOURCES[i..] {
+                actions.push(Action::PlayYearOfPlenty(first, second));
+            }
+            actions.push(Action::PlayMonopoly(first));
+        }
+        for intersection in board.get_intersections() {
+            actions.push(Action::PlaceSettlement(intersection));
+            actions.push(Action::BuildCity(intersection));
+        }
+        for edge in board.get_edges() {
+            actions.push(Action::PlaceRoad(edge));
+        }
+        for (row, column) in board.get_land_hexes() {
+            actions.push(Action::MoveRobber { row, column, victim: None });
+            for victim in self.get_robber_victims(row, column) {
+                actions.push(Action::MoveRobber { row, column, victim: Some(victim) });
+            }
+        }
+        let player = self.get_current_player();
+        let hand = &self.get_player(player).hand;
+        let trading = self.get_phase() == Phase::Trade;
+        if trading {
+            actions.extend(self.get_all_bank_trades(player).into_iter().map(Action::BankTrade));
+            let mut offers = HashSet::new();
+            for other in (0..self.get_players_size()).map(PlayerId) {
+                if other == player {
+                    continue;
+                }
+                for offer in get_swaps(hand, &self.get_player(other).hand) {
+                    if offers.insert(offer) {
+                        actions.push(Action::OfferTrade(offer));
+                    }
+                }
+            }
+        }
+        let mut legal: Vec<(PlayerId, Action)> = actions.into_iter()
+            .filter(|action| self.check(player, action).is_ok())
+            .map(|action| (player, action))
+            .collect();
+        for other in (0..self.get_players_size()).map(PlayerId) {
+            let mut responses = vec![Action::AcceptTrade, Action::RejectTrade];
+            if trading && other != player && self.get_negotiation().is_some() {
+                let counters = get_swaps(hand, &self.get_player(other).hand);
+                responses.extend(counters.into_iter().map(Action::CounterTrade));
+            }
+            for action in responses {
+                if self.check(other, &action).is_ok() {
+                    legal.push((other, action));
+                }
+            }
+        }
+        if self.get_phase() == Phase::Discard {
+            for other in (0..self.get_players_size()).map(PlayerId) {
+                let count = self.get_discard(other);
+                if count == 0 {
+                    continue;
+                }
+                let hand = &self.get_player(other).hand;
+                for cards in get_discards(hand, count, 0) {
+                    legal.push((other, Action::Discard(cards)));
+                }
+            }
+        }
+        legal
+    }
+
+    // Every trade with the bank the player could make now, of any number of lots.
+    fn get_all_bank_trades(&self, player: PlayerId) -> Vec<Offer> {
+        let rates = self.get_bank_trade_rates(player);
+        let mut trades = vec![];
+        for give in get_parts(&self.get_player(player).hand, &rates, 0) {
+            let lots = count_lots(&give, &rates).unwrap_or(0);
+            if lots == 0 {
+                continue;
+            }
+            // The bank's cards of the resources not given.
+            let mut bank = *self.get_bank().get_cards();
+            for &resource in &RESOURCES {
+                if give.get(resource) > 0 {
+                    bank.remove(resource, bank.get(resource));
+                }
+            }
+            for get in get_discards(&bank, lots, 0) {
+                trades.push(Offer::new(give, get));
+            }
+        }
+        trades
+    }
+}
+
+// Every part of the hand, the empty one included, with the count of each resource a multiple of
+// its step, using the resources from index on.
+fn get_parts(hand: &Hand, steps: &[u32; 5], index: usize) -> Vec<Hand> {
+    if index == RESOURCES.len() {
+        return vec![Default::default()];
+    }
+    let resource = RESOURCES[index];
+    let step = steps[index];
+    let mut parts = vec![];
+    for taken in (0..=hand.get(resource) / step).map(|lots| lots * step) {
+        for mut rest in get_parts(hand, steps, index + 1) {
+            rest.add(resource, taken);
+            parts.push(rest);
+        }
+    }
+    parts
+}
+
+// Every valid offer of cards from the first hand for cards from the second.
+fn get_swaps(give: &Hand, get: &Hand) -> Vec<Offer> {
+    let mut offers = vec![];
+    for gives in get_parts(give, &[1; 5], 0) {
+        if gives.is_empty() {
+            continue;
+        }
+        // The other side can't have the resources given.
+        let mut rest = *get;
+        for &resource in &RESOURCES {
+            if gives.get(resource) > 0 {
+                rest.remove(resource, rest.get(resource));
+            }
+        }
+        for gets in get_parts(&rest, &[1; 5], 0) {
+            if !gets.is_empty() {
+                offers.push(Offer::new(gives, gets));
+            }
+        }
+    }
+    offers
+}
+
+// Every way of picking count cards from the hand, using the resources from index on.
+fn get_discards(hand: &Hand, count: u32, index: usize) -> Vec<Hand> {
+    if index == RESOURCES.len() {
+        return if count == 0 { vec![Default::default()] } else { vec![] };
+    }
+    let resource = RESOURCES[index];
+    let mut discards = vec![];
+    for taken in 0..=count.min(hand.get(resource)) {
+        for mut rest in get_discards(hand, count - taken, index + 1) {
+            rest.add(resource, taken);
+            discards.push(rest);
+        }
+    }
+    discards
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use board::{EdgeKind, IntersectionKind};
+    use history::UndoPolicy;
+    use mapgen::generate_base_map;
+    use random::{Random, Randomness};
+    use rules::Rules;
+    use serde_json;
+    use std::collections::HashSet;
+    use std::mem;
+
+    fn make_game() -> GameState {
+        GameState::new(generate_base_map(0, &Default::default()).unwrap(), 2, 0)
+    }
+
+    // A random legal action. The kind of action is picked first, so that the many trades don't
+    // crowd out the rest.
+    fn pick(legal: &[(PlayerId, Action)], random: &mut Random) -> (PlayerId, Action) {
+        let mut kinds: Vec<_> = legal.iter().map(|&(_, action)| mem::discriminant(&action))
+            .collect();
+        kinds.dedup();
+        let kind = kinds[random.below(kinds.len())];
+        let of_kind: Vec<_> = legal.iter()
+            .filter(|&&(_, action)| mem::discriminant(&action) == kind)
+            .collect();
+        *of_kind[random.below(of_kind.len())]
+    }
+
+    // Play the first legal action until the game reaches the phase.
+    fn play_until(game: &mut GameState, phase: Phase) {
+        while game.get_phase() != phase {
+            let (player, action) = game.legal_actions()[0];
+            game.apply(player, &action).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_legal_actions_in_setup() {
+        let mut game = make_game();
+        let actions = game.legal_actions();
+        assert!(actions.iter().all(|&(player, action)| {
+            player == PlayerId(0) && matches!(action, Action::PlaceSettlement(_))
+        }));
+        let land = game.get_board().get_intersections()
+            .into_iter()
+            .filter(|&i| {
+                game.get_board().get_intersection_kind(i) == IntersectionKind::Land
+            })
+            .count();
+        assert_eq!(land, actions.len());
+
+        let (player, action) = actions[0];
+        let intersection = match action {
+            Action::PlaceSettlement(intersection) => intersection,
+            _ => unreachable!(),
+        };
+        assert_eq!(Ok(Outcome::Done), game.apply(player, &action));
+        let roads: Vec<Action> = game.legal_actions().into_iter().map(|(_, a)| a).collect();
+        assert_eq!(game.get_board().get_intersection_edges(intersection)
+                       .into_iter()
+                       .filter(|&e| game.get_board().get_edge_kind(e) != EdgeKind::Sea)
+                       .map(Action::PlaceRoad)
+                       .collect::<Vec<Action>>(),
+                   roads);
+    }
+
+    #[test]
+    fn test_every_legal_action_applies() {
+        let mut game = make_game();
+        play_until(&mut game, Phase::Roll);
+        assert_eq!(vec![(PlayerId(0), Action::Roll)], game.legal_actions());
+        match game.apply(PlayerId(0), &Action::Roll) {
+            Ok(Outcome::Rolled(roll, production)) => {
+                assert!((2..=12).contains(&roll));
+                assert_eq!(roll == 7, production.is_none());
+            }
+            outcome => panic!("unexpected {:?}", outcome),
+        }
+        if game.get_phase() == Phase::MoveRobber {
+            let robber = game.get_board().get_robber().unwrap();
+            let moves = game.legal_actions();
+            assert!(game.get_board().get_land_hexes().len() - 1 <= moves.len());
+            assert!(moves.iter().all(|&(player, action)| match action {
+                Action::MoveRobber { row, column, .. } =>
+                    player == PlayerId(0) && (row, column) != robber,
+                _ => false,
+            }));
+            game.apply(PlayerId(0), &moves[0].1).unwrap();
+        }
+        let actions = game.legal_actions();
+        assert!(actions.contains(&(PlayerId(0), Action::EndTrade)));
+        assert!(actions.contains(&(PlayerId(0), Action::EndTurn)));
+        game.apply(PlayerId(0), &Action::EndTrade).unwrap();
+
+        let actions = game.legal_actions();
+        assert!(!actions.contains(&(PlayerId(0), Action::EndTrade)));
+        assert!(actions.contains(&(PlayerId(0), Action::EndTurn)));
+        assert_eq!(Err(GameError::NotYourTurn(PlayerId(0))),
+                   game.apply(PlayerId(1), &Action::EndTurn));
+        assert_eq!(Err(GameError::WrongPhase(Phase::Build)),
+                   game.apply(PlayerId(0), &Action::Roll));
+    }
+
+    // Every action in a form that could be legal somewhere, trades of a few cards included.
+    fn get_candidates(game: &GameState) -> Vec<Action> {
+        let board = game.get_board();
+        let mut actions = vec![
+            Action::Roll,
+            Action::BuyDevCard,
+            Action::PlayKnight,
+            Action::PlayRoadBuilding,
+            Action::CancelTrade,
+            Action::AcceptTrade,
+            Action::RejectTrade,
+            Action::EndTrade,
+            Action::EndTurn,
+        ];
+        for player in 0..game.get_players_size() {
+            actions.push(Action::ConfirmTrade(PlayerId(player)));
+        }
+        for &first in &RESOURCES {
+            actions.push(Action::PlayMonopoly(first));
+            for &second in &RESOURCES {
+                actions.push(Action::PlayYearOfPlenty(first, second));
+                if first == second {
+                    continue;
+                }
+                for give in 1..9 {
+                    for get in 1..3 {
+                        let offer = Offer::new(Hand::single(first, give),
+                                               Hand::single(second, get));
+                        actions.push(Action::OfferTrade(offer));
+                        actions.push(Action::CounterTrade(offer));
+                        actions.push(Action::BankTrade(offer));
+                    }
+                }
+            }
+        }
+        for intersection in 0..board.get_intersection_size() {
+            actions.push(Action::PlaceSettlement(intersection));
+            actions.push(Action::BuildCity(intersection));
+        }
+        for edge in 0..board.get_edge_size() {
+            actions.push(Action::PlaceRoad(edge));
+        }
+        for (row, column) in board.get_hexes() {
+            actions.push(Action::MoveRobber { row, column, victim: None });
+            for victim in 0..game.get_players_size() {
+                let victim = Some(PlayerId(victim));
+                actions.push(Action::MoveRobber { row, column, victim });
+            }
+        }
+        for count in 0..5 {
+            let discards = get_discards(&Hand([count; 5]), count, 0);
+            actions.extend(discards.into_iter().map(Action::Discard));
+        }
+        actions
+    }
+
+    #[test]
+    fn test_legal_actions_are_complete() {
+        let mut game = make_game();
+        let mut random = Random::new(3);
+        let mut trades = 0;
+        for step in 0..400 {
+            let legal = game.legal_actions();
+            if step % 8 == 0 {
+                let candidates = get_candidates(&game);
+                for player in (0..game.get_players_size()).map(PlayerId) {
+                    for action in &candidates {
+                        if game.check(player, action).is_ok() {
+                            assert!(legal.contains(&(player, *action)), "{:?}", action);
+                        }
+                    }
+                }
+                trades += legal.iter()
+                    .filter(|&&(_, action)| match action {
+                        Action::OfferTrade(_) | Action::CounterTrade(_) => true,
+                        Action::BankTrade(offer) => offer.get.total() > 1,
+                        _ => false,
+                    })
+                    .count();
+            }
+            if legal.is_empty() {
+                break;
+            }
+            let (player, action) = pick(&legal, &mut random);
+            game.apply(player, &action).unwrap();
+        }
+        assert!(trades > 0);
+    }
+
+    #[test]
+    fn test_actions_serialize() {
+        let actions = vec![
+            Action::PlaceSettlement(12),
+            Action::MoveRobber { row: 2, column: 3, victim: Some(PlayerId(1)) },
+            Action::Discard(Hand([0, 1, 0, 0, 2])),
+            Action::EndTurn,
+        ];
+        let json = serde_json::to_string(&actions).unwrap();
+        assert_eq!(concat!(r#"[{"PlaceSettlement":12},"#,
+                           r#"{"MoveRobber":{"row":2,"column":3,"victim":1}},"#,
+                           r#"{"Discard":[0,1,0,0,2]},"EndTurn"]"#),
+                   json);
+        assert_eq!(actions, serde_json::from_str::<Vec<Action>>(&json).unwrap());
+        let json = serde_json::to_string(&(PlayerId(1), Action::Roll)).unwrap();
+        assert_eq!(r#"[1,"Roll"]"#, json);
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut game = make_game();
+        assert_eq!(Err(GameError::NothingToUndo), game.undo());
+        let start = game.snapshot();
+        let (player, action) = game.legal_actions()[0];
+        game.apply(player, &action).unwrap();
+        let placed = game.snapshot();
+        game.undo().unwrap();
+        assert_eq!(start, game.snapshot());
+        assert!(game.get_history().get_records().is_empty());
+        game.redo().unwrap();
+        assert_eq!(placed, game.snapshot());
+        assert_eq!(Err(GameError::NothingToRedo), game.redo());
+
+        // Undoing the roll and rolling again gives the same roll.
+        play_until(&mut game, Phase::Roll);
+        let rolled = game.apply(PlayerId(0), &Action::Roll).unwrap();
+        game.undo().unwrap();
+        assert_eq!(Phase::Roll, game.get_phase());
+        assert_eq!(Ok(rolled), game.apply(PlayerId(0), &Action::Roll));
+        // A new action can't be redone over.
+        game.undo().unwrap();
+        let (player, action) = game.legal_actions()[0];
+        game.apply(player, &action).unwrap();
+        assert_eq!(Err(GameError::NothingToRedo), game.redo());
+    }
+
+    #[test]
+    fn test_undo_every_action() {
+        let mut game = make_game();
+        let mut random = Random::new(5);
+        let mut kinds = HashSet::new();
+        for _ in 0..1500 {
+            let legal = game.legal_actions();
+            if legal.is_empty() {
+                break;
+            }
+            let (player, action) = pick(&legal, &mut random);
+            let before = game.snapshot();
+            game.apply(player, &action).unwrap();
+            let after = game.snapshot();
+            game.undo().unwrap();
+            assert_eq!(before, game.snapshot(), "undoing {:?}", action);
+            game.redo().unwrap();
+            assert_eq!(after, game.snapshot(), "redoing {:?}", action);
+            kinds.insert(mem::discriminant(&action));
+        }
+        assert!(kinds.len() >= 19, "{} kinds of actions", kinds.len());
+    }
+
+    #[test]
+    fn test_competitive_undo() {
+        let rules = Rules {
+            undo: UndoPolicy::Competitive,
+            ..Default::default()
+        };
+        let board = generate_base_map(0, &Default::default()).unwrap();
+        let mut game = GameState::with_rules(board, 2, 0, rules);
+        play_until(&mut game, Phase::Roll);
+        game.undo().unwrap();
+        play_until(&mut game, Phase::Roll);
+        game.apply(PlayerId(0), &Action::Roll).unwrap();
+        assert_eq!(Err(GameError::UndoBlocked), game.undo());
+        assert_eq!(Some(&Action::Roll), game.get_history().get_last().map(|r| &r.action));
+    }
+
+    #[test]
+    fn test_legal_discards() {
+        let hand = Hand([2, 0, 1, 0, 0]);
+        let discards = get_discards(&hand, 2, 0);
+        assert_eq!(2, discards.len());
+        assert!(discards.contains(&Hand([2, 0, 0, 0, 0])));
+        assert!(discards.contains(&Hand([1, 0, 1, 0, 0])));
+        assert!(get_discards(&hand, 4, 0).is_empty());
+    }
+}
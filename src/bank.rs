@@ -0,0 +1,62 @@
+// The bank holds the resource cards nobody has. There are only 19 cards of each resource, so
+// the bank can run out.
+
+use hand::Hand;
+use terrain::{Resource, RESOURCES};
+
+pub const BANK_CARDS: u32 = 19;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bank {
+    cards: Hand,
+}
+
+impl Default for Bank {
+    fn default() -> Bank {
+        Bank {
+            cards: Hand([BANK_CARDS; 5]),
+        }
+    }
+}
+
+impl Bank {
+    pub fn get_cards(&self) -> &Hand {
+        &self.cards
+    }
+
+    pub fn get(&self, resource: Resource) -> u32 {
+        self.cards.get(resource)
+    }
+
+    pub fn has(&self, hand: &Hand) -> bool {
+        self.cards.contains(hand)
+    }
+
+    // Hand out the cards. The bank has to have them.
+    pub fn pay(&mut self, hand: &Hand) {
+        self.cards.remove_hand(hand);
+    }
+
+    // Take the cards back.
+    pub fn receive(&mut self, hand: &Hand) {
+        self.cards.add_hand(hand);
+        debug_assert!(RESOURCES.iter().all(|&resource| self.get(resource) <= BANK_CARDS));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hand::ROAD_COST;
+
+    #[test]
+    fn test_bank() {
+        let mut bank: Bank = Default::default();
+        assert_eq!(19, bank.get(Resource::Ore));
+        bank.pay(&ROAD_COST);
+        assert_eq!(18, bank.get(Resource::Brick));
+        assert!(!bank.has(&Hand::single(Resource::Lumber, 19)));
+        bank.receive(&ROAD_COST);
+        assert_eq!(Hand([19; 5]), *bank.get_cards());
+    }
+}
@@ -0,0 +1,1331 @@
+// Represent hexagons with axial coordinates like this
+//
+//                / \ / \ / \
+//               |0,0|0,1|0,2|
+//                \ / \ / \ / \
+//                 |1,0|1,1|1,2|
+//                  \ / \ / \ / \
+//                   |2,0|2,1|2,2|
+//                    \ / \ / \ /
+//
+// Each hex is referenced by the tuple (row, column).
+//
+// The intersections are indexed like this
+//
+//             0   1   2   3
+//              \4/ \5/ \6/ \7
+//               |   |   |   |
+//               8\C/9\D/A\E/B\F
+//                 |   |   |   |
+//                 G\K/H\L/I\M/J\N
+//                   |   |   |   |
+//                   O\ /P\ /Q\ /R\
+//                     S   T   U   V
+//
+// Each row of intersections is a zigzag. The first half of a row are the top corners and the
+// second half are the bottom corners. Some of them, like 0 and V, don't touch any hex.
+//
+// There are three kinds of edges: vertical, right, left.
+// They are indexed like this
+//
+// Vertical
+//             0   1   2   3
+//             |   |   |   |
+//            / \4/ \5/ \6/ \7/
+//               |   |   |   |
+//              / \8/ \9/ \A/ \B/
+//                 |   |   |   |
+//                / \C/ \D/ \E/ \F/
+//                   |   |   |   |
+//                  / \G/ \H/ \I/ \J/
+//                     |   |   |   |
+//
+// Right
+//
+//             |   |   |   |
+//            / \0/ \1/ \2/ \3/
+//               |   |   |   |
+//              / \4/ \5/ \6/ \7/
+//                 |   |   |   |
+//                / \8/ \9/ \A/ \B/
+//                   |   |   |   |
+//                  / \C/ \D/ \E/ \F/
+//                     |   |   |   |
+//
+// Left
+//
+//             |   |   |   |
+//           0/ \1/ \2/ \3/ \4/
+//               |   |   |   |
+//             5/ \6/ \7/ \8/ \9/
+//                 |   |   |   |
+//               A/ \B/ \C/ \D/ \E/
+//                   |   |   |   |
+//                 F/ \G/ \H/ \I/ \J/
+//                     |   |   |   |
+//
+// The edges are stored in one array: the vertical edges first, then the right edges, then the
+// left edges. Like the intersections, the edges on the border of the arrays don't touch any hex.
+//
+// The six corners and six sides of a hex are always listed clockwise starting from the north
+// corner and the northeast side respectively.
+//
+//                 N
+//           NW  /   \  NE
+//         NW  /       \  NE
+//            |         |
+//          W |         | E
+//            |         |
+//         SW  \       /  SE
+//           SW  \   /  SE
+//                 S
+
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+
+use harbor::{Harbor, BANK_RATE};
+use piece::{Building, PlayerId, Road, RoadKind};
+use shape::{HexKind, Shape};
+use terrain::{NumberToken, Resource, TerrainKind, RESOURCES};
+
+// The kinds of intersections.
+// Invalid means they're not used.
+// Sea means only sea hexes meet there. Ships can pass but you can't build on them.
+// Land means some land hex meets there, so you can build settlements and cities on them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IntersectionKind {
+    #[default]
+    Invalid,
+    Sea,
+    Land,
+}
+
+// The kinds of edges.
+// Invalid means they're not used.
+// Sea means there is no land on either side.
+// Coast means there is land on exactly one side.
+// Land means there is land on both sides.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EdgeKind {
+    #[default]
+    Invalid,
+    Sea,
+    Coast,
+    Land,
+}
+
+// A hex of the board. Off-board hexes keep the default terrain.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Terrain {
+    pub kind: HexKind,
+    pub tile: TerrainKind,
+    pub token: Option<NumberToken>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Intersection {
+    pub building: Option<Building>,
+    pub kind: IntersectionKind,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Edge {
+    pub kind: EdgeKind,
+    pub road: Option<Road>,
+    // Only coastal edges have harbors.
+    pub harbor: Option<Harbor>,
+}
+
+// Loading a board checks its layout against the height and width, see Layout.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "Layout")]
+pub struct Board {
+    height: usize,
+    width: usize,
+    mem_height: usize,
+    mem_width: usize,
+    vertical_edge_size: usize,
+    right_edge_size: usize,
+    terrains: Vec<Terrain>,
+    intersections: Vec<Intersection>,
+    edges: Vec<Edge>,
+    // The land hex the robber is on, if it has been put on the board.
+    robber: Option<(usize, usize)>,
+}
+
+// A board as it was saved, before its layout is checked.
+#[derive(Deserialize)]
+struct Layout {
+    height: usize,
+    width: usize,
+    mem_height: usize,
+    mem_width: usize,
+    vertical_edge_size: usize,
+    right_edge_size: usize,
+    terrains: Vec<Terrain>,
+    intersections: Vec<Intersection>,
+    edges: Vec<Edge>,
+    robber: Option<(usize, usize)>,
+}
+
+impl TryFrom<Layout> for Board {
+    type Error = LayoutError;
+
+    // The sizes have to be the ones init_shape gives a board of the height and width.
+    fn try_from(layout: Layout) -> Result<Board, LayoutError> {
+        let (height, width) = (layout.height, layout.width);
+        if height == 0 || width == 0 {
+            return Err(LayoutError::Empty);
+        }
+        let vertical_edge_size = (height + 2) * (width + 1);
+        let right_edge_size = (height + 1) * (width + 1);
+        let left_edge_size = (height + 1) * (width + 2);
+        let sizes = [
+            ("mem_height", height, layout.mem_height),
+            ("mem_width", width + 2, layout.mem_width),
+            ("vertical_edge_size", vertical_edge_size, layout.vertical_edge_size),
+            ("right_edge_size", right_edge_size, layout.right_edge_size),
+            ("terrains", height * (width + 2), layout.terrains.len()),
+            ("intersections", 2 * (height + 1) * (width + 1), layout.intersections.len()),
+            ("edges", vertical_edge_size + right_edge_size + left_edge_size,
+             layout.edges.len()),
+        ];
+        for &(name, expected, found) in &sizes {
+            if expected != found {
+                return Err(LayoutError::WrongSize { name, expected, found });
+            }
+        }
+        let board = Board {
+            height,
+            width,
+            mem_height: layout.mem_height,
+            mem_width: layout.mem_width,
+            vertical_edge_size,
+            right_edge_size,
+            terrains: layout.terrains,
+            intersections: layout.intersections,
+            edges: layout.edges,
+            robber: layout.robber,
+        };
+        if let Some((row, column)) = board.robber {
+            if row >= height || column >= width || !board.is_land(row, column) {
+                return Err(LayoutError::RobberOffLand(row, column));
+            }
+        }
+        // The kinds follow from the terrains, so they are derived again rather than trusted.
+        let mut derived = board.clone();
+        derived.derive_kinds();
+        for (index, intersection) in board.intersections.iter().enumerate() {
+            if intersection.kind != derived.intersections[index].kind {
+                return Err(LayoutError::WrongIntersectionKind(index));
+            }
+            if intersection.building.is_some() && intersection.kind != IntersectionKind::Land {
+                return Err(LayoutError::BuildingOffLand(index));
+            }
+        }
+        for (index, edge) in board.edges.iter().enumerate() {
+            if edge.kind != derived.edges[index].kind {
+                return Err(LayoutError::WrongEdgeKind(index));
+            }
+            if edge.harbor.is_some() && edge.kind != EdgeKind::Coast {
+                return Err(LayoutError::HarborOffCoast(index));
+            }
+            let fits = match edge.road.map(|road| road.kind) {
+                Some(RoadKind::Road) => matches!(edge.kind, EdgeKind::Land | EdgeKind::Coast),
+                Some(RoadKind::Ship) => matches!(edge.kind, EdgeKind::Sea | EdgeKind::Coast),
+                None => true,
+            };
+            if !fits {
+                return Err(LayoutError::MisplacedRoad(index));
+            }
+        }
+        Ok(board)
+    }
+}
+
+// The error for lookups that start from or land on something that is not on the board.
+// Hex coordinates are signed so that probes past the border can be reported as they were given.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BoardError {
+    HexOffBoard(isize, isize),
+    IntersectionOffBoard(usize),
+    EdgeOffBoard(usize),
+}
+
+impl fmt::Display for BoardError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BoardError::HexOffBoard(row, column) =>
+                write!(f, "hex ({}, {}) is not on the board", row, column),
+            BoardError::IntersectionOffBoard(intersection) =>
+                write!(f, "intersection {} is not on the board", intersection),
+            BoardError::EdgeOffBoard(edge) =>
+                write!(f, "edge {} is not on the board", edge),
+        }
+    }
+}
+
+impl Error for BoardError {}
+
+// The error for a saved board or game whose sizes don't fit together, like a board whose edges
+// don't fit its height and width, or a game with a discard count missing for some player.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LayoutError {
+    Empty,
+    WrongSize { name: &'static str, expected: usize, found: usize },
+    RobberOffLand(usize, usize),
+    // The kind saved for the intersection or edge isn't the one its hexes give it.
+    WrongIntersectionKind(usize),
+    WrongEdgeKind(usize),
+    BuildingOffLand(usize),
+    HarborOffCoast(usize),
+    // A road off land or a ship off the sea.
+    MisplacedRoad(usize),
+    NoPlayers,
+    NoSuchPlayer(PlayerId),
+}
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LayoutError::Empty => write!(f, "the board has no hexes"),
+            LayoutError::WrongSize { name, expected, found } =>
+                write!(f, "wrong {}: expected {}, found {}", name, expected, found),
+            LayoutError::RobberOffLand(row, column) =>
+                write!(f, "the robber is on ({}, {}), which is not a land hex", row, column),
+            LayoutError::WrongIntersectionKind(intersection) =>
+                write!(f, "intersection {} has the wrong kind", intersection),
+            LayoutError::WrongEdgeKind(edge) => write!(f, "edge {} has the wrong kind", edge),
+            LayoutError::BuildingOffLand(intersection) =>
+                write!(f, "the building on intersection {} is not on land", intersection),
+            LayoutError::HarborOffCoast(edge) =>
+                write!(f, "the harbor on edge {} is not on the coast", edge),
+            LayoutError::MisplacedRoad(edge) =>
+                write!(f, "the road on edge {} can't be built there", edge),
+            LayoutError::NoPlayers => write!(f, "the game has no players"),
+            LayoutError::NoSuchPlayer(player) => write!(f, "there is no {}", player),
+        }
+    }
+}
+
+impl Error for LayoutError {}
+
+// The directions to the six neighbors of a hex, clockwise from the northeast.
+// The neighbor in a direction shares the side of the same name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HexDirection {
+    Northeast,
+    East,
+    Southeast,
+    Southwest,
+    West,
+    Northwest,
+}
+
+pub const HEX_DIRECTIONS: [HexDirection; 6] = [
+    HexDirection::Northeast,
+    HexDirection::East,
+    HexDirection::Southeast,
+    HexDirection::Southwest,
+    HexDirection::West,
+    HexDirection::Northwest,
+];
+
+impl HexDirection {
+    // The change in (row, column) to get to the neighbor.
+    pub fn get_offset(&self) -> (isize, isize) {
+        match *self {
+            HexDirection::Northeast => (-1, 1),
+            HexDirection::East => (0, 1),
+            HexDirection::Southeast => (1, 0),
+            HexDirection::Southwest => (1, -1),
+            HexDirection::West => (0, -1),
+            HexDirection::Northwest => (-1, 0),
+        }
+    }
+
+    // The index of the side facing this direction in get_hex_edges.
+    pub fn get_side(&self) -> usize {
+        HEX_DIRECTIONS.iter().position(|d| d == self).unwrap()
+    }
+
+    pub fn opposite(&self) -> HexDirection {
+        HEX_DIRECTIONS[(self.get_side() + 3) % 6]
+    }
+}
+
+// The three kinds of edges in the diagrams above.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum EdgeDirection {
+    Vertical,
+    Right,
+    Left,
+}
+
+impl Board {
+    // Initialize a hexagonal grid in the shape of a rectangle.
+    pub fn init(&mut self, height: usize, width: usize) {
+        self.init_shape(&Shape::rectangle(height, width));
+    }
+
+    // Initialize a hexagonal grid with the hexes marked by the shape.
+    // The kinds of the intersections and edges are derived from the hexes around them.
+    pub fn init_shape(&mut self, shape: &Shape) {
+        let height = shape.get_height();
+        let width = shape.get_width();
+        assert!(0 < height);
+        assert!(0 < width);
+
+        let mem_height = height;
+        let mem_width = 1 + width + 1;
+
+        let terrain_size = mem_height * mem_width;
+        for _ in 0..terrain_size {
+            let terrain: Terrain = Default::default();
+            self.terrains.push(terrain);
+        }
+
+        let intersection_size = 2 * (height + 1) * (width + 1);
+        for _ in 0..intersection_size {
+            let intersection: Intersection = Default::default();
+            self.intersections.push(intersection);
+        }
+
+        let vertical_edge_size = (height + 2) * (width + 1);
+        // The number of edges going down from left to right.
+        let right_edge_size = (height + 1) * (width + 1);
+        let left_edge_size = (height + 1) * (width + 2);
+        let edge_size =
+            vertical_edge_size +
+            right_edge_size +
+            left_edge_size;
+        for _ in 0..edge_size {
+            let edge: Edge = Default::default();
+            self.edges.push(edge);
+        }
+
+        self.height = height;
+        self.width = width;
+        self.mem_height = mem_height;
+        self.mem_width = mem_width;
+        self.vertical_edge_size = vertical_edge_size;
+        self.right_edge_size = right_edge_size;
+
+        for row in 0..height {
+            for column in 0..width {
+                let index = self.get_terrain_index(row, column);
+                let kind = shape.get_hex_kind(row, column);
+                self.terrains[index].kind = kind;
+                if kind == HexKind::Sea {
+                    self.terrains[index].tile = TerrainKind::Sea;
+                }
+            }
+        }
+        self.derive_kinds();
+    }
+
+    // Mark the intersections and edges by the hexes around them, like make_hexagon did for the
+    // triangle grid.
+    fn derive_kinds(&mut self) {
+        for intersection in 0..self.intersections.len() {
+            let hexes = self.get_intersection_hexes(intersection);
+            let kind = if hexes.is_empty() {
+                IntersectionKind::Invalid
+            } else if hexes.iter().any(|&(row, column)| self.is_land(row, column)) {
+                IntersectionKind::Land
+            } else {
+                IntersectionKind::Sea
+            };
+            self.intersections[intersection].kind = kind;
+        }
+        for edge in 0..self.edges.len() {
+            let hexes = self.get_edge_hexes(edge);
+            let land = hexes.iter().filter(|&&(row, column)| self.is_land(row, column)).count();
+            let kind = match (hexes.len(), land) {
+                (0, _) => EdgeKind::Invalid,
+                (_, 0) => EdgeKind::Sea,
+                (2, 2) => EdgeKind::Land,
+                _ => EdgeKind::Coast,
+            };
+            self.edges[edge].kind = kind;
+            if kind != EdgeKind::Coast {
+                self.edges[edge].harbor = None;
+            }
+        }
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+
+    pub fn get_terrain_index(&self, row: usize, column:usize) -> usize {
+        row * self.mem_width + column
+    }
+
+    pub fn get_hex_kind(&self, row: usize, column: usize) -> HexKind {
+        self.get_terrain(row, column).kind
+    }
+
+    pub fn is_land(&self, row: usize, column: usize) -> bool {
+        self.get_hex_kind(row, column) == HexKind::Land
+    }
+
+    pub fn get_tile(&self, row: usize, column: usize) -> TerrainKind {
+        self.get_terrain(row, column).tile
+    }
+
+    pub fn get_token(&self, row: usize, column: usize) -> Option<NumberToken> {
+        self.get_terrain(row, column).token
+    }
+
+    // Lay a terrain tile and its number token on a hex of the board.
+    // Laying sea on land or land on sea changes the kinds of the intersections and edges around it.
+    pub fn set_terrain(&mut self, row: usize, column: usize, tile: TerrainKind,
+                       token: Option<NumberToken>) {
+        assert!(self.get_hex(row as isize, column as isize).is_some());
+        assert!(token.is_none() || tile.is_productive());
+        let index = self.get_terrain_index(row, column);
+        let kind = tile.get_hex_kind();
+        let changed = self.terrains[index].kind != kind;
+        self.terrains[index] = Terrain { kind, tile, token };
+        if changed {
+            self.derive_kinds();
+        }
+    }
+
+    // Get the hexes with the number token, in row major order.
+    pub fn get_hexes_with_number(&self, number: u8) -> Vec<(usize, usize)> {
+        self.get_hexes()
+            .into_iter()
+            .filter(|&(row, column)| {
+                self.get_token(row, column).map(|token| token.get_number()) == Some(number)
+            })
+            .collect()
+    }
+
+    pub fn get_robber(&self) -> Option<(usize, usize)> {
+        self.robber
+    }
+
+    // Move the robber to a land hex.
+    pub fn set_robber(&mut self, row: usize, column: usize) {
+        assert!(self.get_hex(row as isize, column as isize).is_some());
+        assert!(self.is_land(row, column));
+        self.robber = Some((row, column));
+    }
+
+    pub fn get_building(&self, intersection: usize) -> Option<Building> {
+        self.intersections[intersection].building
+    }
+
+    // Put a building on an intersection, or take it off with None.
+    // This doesn't check the rules for building. Buildings only go where there is land.
+    pub fn set_building(&mut self, intersection: usize, building: Option<Building>) {
+        assert!(self.get_intersection_kind(intersection) == IntersectionKind::Land);
+        self.intersections[intersection].building = building;
+    }
+
+    pub fn get_road(&self, edge: usize) -> Option<Road> {
+        self.edges[edge].road
+    }
+
+    // Put a road or ship on an edge, or take it off with None.
+    // This doesn't check the rules for building.
+    pub fn set_road(&mut self, edge: usize, road: Option<Road>) {
+        assert!(self.is_edge_on_board(edge));
+        self.edges[edge].road = road;
+    }
+
+    pub fn get_harbor(&self, edge: usize) -> Option<Harbor> {
+        self.edges[edge].harbor
+    }
+
+    // Put a harbor on a coastal edge, or take it off with None.
+    pub fn set_harbor(&mut self, edge: usize, harbor: Option<Harbor>) {
+        assert!(self.get_edge_kind(edge) == EdgeKind::Coast);
+        self.edges[edge].harbor = harbor;
+    }
+
+    // Get the edges with a harbor and their harbors.
+    pub fn get_harbors(&self) -> Vec<(usize, Harbor)> {
+        (0..self.edges.len())
+            .filter_map(|edge| self.get_harbor(edge).map(|harbor| (edge, harbor)))
+            .collect()
+    }
+
+    // Get the harbors that can be used from the intersection.
+    pub fn get_intersection_harbors(&self, intersection: usize) -> Vec<Harbor> {
+        self.get_intersection_edges(intersection)
+            .into_iter()
+            .filter_map(|edge| self.get_harbor(edge))
+            .collect()
+    }
+
+    // The fewest of the resource the player has to give the bank for one of another, using the
+    // harbors the player has a settlement or city on.
+    pub fn get_trade_rate(&self, player: PlayerId, resource: Resource) -> u32 {
+        self.get_buildings(player)
+            .into_iter()
+            .flat_map(|intersection| self.get_intersection_harbors(intersection))
+            .filter_map(|harbor| harbor.get_rate(resource))
+            .fold(BANK_RATE, |best, rate| best.min(rate))
+    }
+
+    // The trade rates of the player for every resource, indexed by Resource::index.
+    pub fn get_trade_rates(&self, player: PlayerId) -> [u32; 5] {
+        let mut rates = [BANK_RATE; 5];
+        for resource in &RESOURCES {
+            rates[resource.index()] = self.get_trade_rate(player, *resource);
+        }
+        rates
+    }
+
+    pub fn get_intersection_owner(&self, intersection: usize) -> Option<PlayerId> {
+        self.get_building(intersection).map(|building| building.owner)
+    }
+
+    pub fn get_edge_owner(&self, edge: usize) -> Option<PlayerId> {
+        self.get_road(edge).map(|road| road.owner)
+    }
+
+    // Get the intersections with a building of the player.
+    pub fn get_buildings(&self, player: PlayerId) -> Vec<usize> {
+        (0..self.intersections.len())
+            .filter(|&intersection| self.get_intersection_owner(intersection) == Some(player))
+            .collect()
+    }
+
+    // Get the edges with a road or ship of the player.
+    pub fn get_roads(&self, player: PlayerId) -> Vec<usize> {
+        (0..self.edges.len()).filter(|&edge| self.get_edge_owner(edge) == Some(player)).collect()
+    }
+
+    // Get the edges meeting at the intersection that have a road or ship of any player.
+    pub fn get_adjacent_roads(&self, intersection: usize) -> Vec<usize> {
+        self.get_intersection_edges(intersection)
+            .into_iter()
+            .filter(|&edge| self.get_road(edge).is_some())
+            .collect()
+    }
+
+    // Get the buildings on the corners of the hex with the intersections they are on.
+    pub fn get_hex_buildings(&self, row: usize, column: usize) -> Vec<(usize, Building)> {
+        self.get_hex_intersections(row, column)
+            .iter()
+            .filter_map(|&intersection| {
+                self.get_building(intersection).map(|building| (intersection, building))
+            })
+            .collect()
+    }
+
+    pub fn get_intersection_kind(&self, intersection: usize) -> IntersectionKind {
+        self.intersections[intersection].kind
+    }
+
+    pub fn get_edge_kind(&self, edge: usize) -> EdgeKind {
+        self.edges[edge].kind
+    }
+
+    // Get the hexes that are on the board in row major order.
+    pub fn get_hexes(&self) -> Vec<(usize, usize)> {
+        let mut hexes = vec![];
+        for row in 0..self.height {
+            for column in 0..self.width {
+                if self.get_hex_kind(row, column) != HexKind::OffBoard {
+                    hexes.push((row, column));
+                }
+            }
+        }
+        hexes
+    }
+
+    pub fn get_land_hexes(&self) -> Vec<(usize, usize)> {
+        self.get_hexes().into_iter().filter(|&(row, column)| self.is_land(row, column)).collect()
+    }
+
+    // Get the intersections that are on the board.
+    pub fn get_intersections(&self) -> Vec<usize> {
+        (0..self.intersections.len())
+            .filter(|&intersection| self.is_intersection_on_board(intersection))
+            .collect()
+    }
+
+    // Get the edges that are on the board.
+    pub fn get_edges(&self) -> Vec<usize> {
+        (0..self.edges.len()).filter(|&edge| self.is_edge_on_board(edge)).collect()
+    }
+
+    pub fn get_terrain(&self, row: usize, column: usize) -> &Terrain {
+        assert!(row < self.mem_height);
+        &self.terrains[self.get_terrain_index(row, column)]
+    }
+
+    pub fn get_intersection(&self, intersection: usize) -> &Intersection {
+        &self.intersections[intersection]
+    }
+
+    pub fn get_edge(&self, edge: usize) -> &Edge {
+        &self.edges[edge]
+    }
+
+    pub fn get_intersection_size(&self) -> usize {
+        self.intersections.len()
+    }
+
+    pub fn get_edge_size(&self) -> usize {
+        self.edges.len()
+    }
+
+    // The intersections in a row of the diagram are split in half.
+    // The top corners come first and then the bottom corners.
+    fn get_top_intersection_index(&self, row: usize, offset: usize) -> usize {
+        2 * (self.width + 1) * row + offset
+    }
+
+    fn get_bottom_intersection_index(&self, row: usize, offset: usize) -> usize {
+        2 * (self.width + 1) * row + (self.width + 1) + offset
+    }
+
+    // Decode an intersection index into (row, offset, is_top).
+    fn decode_intersection(&self, intersection: usize) -> (usize, usize, bool) {
+        let row_size = 2 * (self.width + 1);
+        let row = intersection / row_size;
+        let offset = intersection % row_size;
+        if offset <= self.width {
+            (row, offset, true)
+        } else {
+            (row, offset - (self.width + 1), false)
+        }
+    }
+
+    fn get_vertical_edge_index(&self, row: usize, offset: usize) -> usize {
+        row * (self.width + 1) + offset
+    }
+
+    fn get_right_edge_index(&self, row: usize, offset: usize) -> usize {
+        self.vertical_edge_size + row * (self.width + 1) + offset
+    }
+
+    fn get_left_edge_index(&self, row: usize, offset: usize) -> usize {
+        self.vertical_edge_size + self.right_edge_size + row * (self.width + 2) + offset
+    }
+
+    // Decode an edge index into its direction and its (row, offset) in the diagram.
+    fn decode_edge(&self, edge: usize) -> (EdgeDirection, usize, usize) {
+        if edge < self.vertical_edge_size {
+            let row_size = self.width + 1;
+            (EdgeDirection::Vertical, edge / row_size, edge % row_size)
+        } else if edge < self.vertical_edge_size + self.right_edge_size {
+            let offset = edge - self.vertical_edge_size;
+            let row_size = self.width + 1;
+            (EdgeDirection::Right, offset / row_size, offset % row_size)
+        } else {
+            let offset = edge - self.vertical_edge_size - self.right_edge_size;
+            let row_size = self.width + 2;
+            (EdgeDirection::Left, offset / row_size, offset % row_size)
+        }
+    }
+
+    // Return the hex at (row, column) if it is on the board.
+    // The coordinates are signed so that callers can step off the grid without underflow.
+    pub fn get_hex(&self, row: isize, column: isize) -> Option<(usize, usize)> {
+        if row < 0 || column < 0 {
+            return None;
+        }
+        let (row, column) = (row as usize, column as usize);
+        if row < self.height && column < self.width &&
+            self.get_hex_kind(row, column) != HexKind::OffBoard {
+            Some((row, column))
+        } else {
+            None
+        }
+    }
+
+    // Return the hexes from the list that are on the board.
+    fn filter_hexes(&self, hexes: &[(isize, isize)]) -> Vec<(usize, usize)> {
+        hexes.iter()
+            .filter_map(|&(row, column)| self.get_hex(row, column))
+            .collect()
+    }
+
+    // An intersection is on the board if it is the corner of a hex.
+    pub fn is_intersection_on_board(&self, intersection: usize) -> bool {
+        intersection < self.intersections.len() &&
+            self.get_intersection_kind(intersection) != IntersectionKind::Invalid
+    }
+
+    // An edge is on the board if it is the side of a hex.
+    pub fn is_edge_on_board(&self, edge: usize) -> bool {
+        edge < self.edges.len() && self.get_edge_kind(edge) != EdgeKind::Invalid
+    }
+
+    // Get the corners of the hex at (row, column) clockwise from the north.
+    pub fn get_hex_intersections(&self, row: usize, column: usize) -> [usize; 6] {
+        assert!(row < self.height);
+        assert!(column < self.width);
+        [
+            self.get_top_intersection_index(row, column + 1),
+            self.get_bottom_intersection_index(row, column + 1),
+            self.get_top_intersection_index(row + 1, column + 1),
+            self.get_bottom_intersection_index(row + 1, column),
+            self.get_top_intersection_index(row + 1, column),
+            self.get_bottom_intersection_index(row, column),
+        ]
+    }
+
+    // Get the sides of the hex at (row, column) clockwise from the northeast.
+    pub fn get_hex_edges(&self, row: usize, column: usize) -> [usize; 6] {
+        assert!(row < self.height);
+        assert!(column < self.width);
+        [
+            self.get_right_edge_index(row, column + 1),
+            self.get_vertical_edge_index(row + 1, column + 1),
+            self.get_left_edge_index(row + 1, column + 1),
+            self.get_right_edge_index(row + 1, column),
+            self.get_vertical_edge_index(row + 1, column),
+            self.get_left_edge_index(row, column + 1),
+        ]
+    }
+
+    // Get the edges meeting at the intersection that are on the board.
+    // For a top corner they are ordered north, southeast, southwest.
+    // For a bottom corner they are ordered northwest, northeast, south.
+    pub fn get_intersection_edges(&self, intersection: usize) -> Vec<usize> {
+        assert!(intersection < self.intersections.len());
+        let (row, offset, is_top) = self.decode_intersection(intersection);
+        let edges = if is_top {
+            [
+                self.get_vertical_edge_index(row, offset),
+                self.get_right_edge_index(row, offset),
+                self.get_left_edge_index(row, offset),
+            ]
+        } else {
+            [
+                self.get_right_edge_index(row, offset),
+                self.get_left_edge_index(row, offset + 1),
+                self.get_vertical_edge_index(row + 1, offset),
+            ]
+        };
+        edges.iter()
+            .cloned()
+            .filter(|&edge| self.is_edge_on_board(edge))
+            .collect()
+    }
+
+    // Get the hexes that have the intersection as a corner.
+    pub fn get_intersection_hexes(&self, intersection: usize) -> Vec<(usize, usize)> {
+        assert!(intersection < self.intersections.len());
+        let (row, offset, is_top) = self.decode_intersection(intersection);
+        let (row, offset) = (row as isize, offset as isize);
+        if is_top {
+            // The hex below, then the hexes to the northwest and northeast.
+            self.filter_hexes(&[(row, offset - 1), (row - 1, offset - 1), (row - 1, offset)])
+        } else {
+            // The hexes to the southwest and southeast, then the hex above.
+            self.filter_hexes(&[(row, offset - 1), (row, offset), (row - 1, offset)])
+        }
+    }
+
+    // Get the intersections that share an edge with the intersection.
+    // They are in the same order as the edges from get_intersection_edges.
+    pub fn get_intersection_neighbors(&self, intersection: usize) -> Vec<usize> {
+        self.get_intersection_edges(intersection)
+            .iter()
+            .map(|&edge| {
+                let (a, b) = self.get_edge_endpoints(edge);
+                if a == intersection { b } else { a }
+            })
+            .collect()
+    }
+
+    // Get the endpoints of the edge. The upper endpoint comes first.
+    pub fn get_edge_endpoints(&self, edge: usize) -> (usize, usize) {
+        assert!(self.is_edge_on_board(edge));
+        let (direction, row, offset) = self.decode_edge(edge);
+        match direction {
+            EdgeDirection::Vertical => (
+                self.get_bottom_intersection_index(row - 1, offset),
+                self.get_top_intersection_index(row, offset),
+            ),
+            EdgeDirection::Right => (
+                self.get_top_intersection_index(row, offset),
+                self.get_bottom_intersection_index(row, offset),
+            ),
+            EdgeDirection::Left => (
+                self.get_top_intersection_index(row, offset),
+                self.get_bottom_intersection_index(row, offset - 1),
+            ),
+        }
+    }
+
+    // Get the hexes that have the edge as a side.
+    // The hex below or to the west comes first.
+    pub fn get_edge_hexes(&self, edge: usize) -> Vec<(usize, usize)> {
+        assert!(edge < self.edges.len());
+        let (direction, row, offset) = self.decode_edge(edge);
+        let (row, offset) = (row as isize, offset as isize);
+        match direction {
+            EdgeDirection::Vertical =>
+                self.filter_hexes(&[(row - 1, offset - 1), (row - 1, offset)]),
+            EdgeDirection::Right =>
+                self.filter_hexes(&[(row, offset - 1), (row - 1, offset)]),
+            EdgeDirection::Left =>
+                self.filter_hexes(&[(row, offset - 1), (row - 1, offset - 1)]),
+        }
+    }
+
+    pub fn is_hex_on_board(&self, row: isize, column: isize) -> bool {
+        self.get_hex(row, column).is_some()
+    }
+
+    // The checked lookups below return an error instead of panicking when given something that
+    // is not on the board.
+
+    pub fn check_hex(&self, row: isize, column: isize) -> Result<(usize, usize), BoardError> {
+        self.get_hex(row, column).ok_or(BoardError::HexOffBoard(row, column))
+    }
+
+    pub fn check_intersection(&self, intersection: usize) -> Result<usize, BoardError> {
+        if self.is_intersection_on_board(intersection) {
+            Ok(intersection)
+        } else {
+            Err(BoardError::IntersectionOffBoard(intersection))
+        }
+    }
+
+    pub fn check_edge(&self, edge: usize) -> Result<usize, BoardError> {
+        if self.is_edge_on_board(edge) {
+            Ok(edge)
+        } else {
+            Err(BoardError::EdgeOffBoard(edge))
+        }
+    }
+
+    // Get the neighbor of the hex in the given direction.
+    // The result is None when the neighbor is past the border of the board.
+    pub fn get_hex_neighbor(&self, row: isize, column: isize, direction: HexDirection)
+        -> Result<Option<(usize, usize)>, BoardError> {
+        let (row, column) = self.check_hex(row, column)?;
+        let (row_offset, column_offset) = direction.get_offset();
+        Ok(self.get_hex(row as isize + row_offset, column as isize + column_offset))
+    }
+
+    // Get the neighbors of the hex that are on the board, clockwise from the northeast.
+    pub fn get_hex_neighbors(&self, row: isize, column: isize)
+        -> Result<Vec<(usize, usize)>, BoardError> {
+        let mut neighbors = vec![];
+        for direction in &HEX_DIRECTIONS {
+            if let Some(hex) = self.get_hex_neighbor(row, column, *direction)? {
+                neighbors.push(hex);
+            }
+        }
+        Ok(neighbors)
+    }
+
+    pub fn try_get_hex_intersections(&self, row: isize, column: isize)
+        -> Result<[usize; 6], BoardError> {
+        let (row, column) = self.check_hex(row, column)?;
+        Ok(self.get_hex_intersections(row, column))
+    }
+
+    pub fn try_get_hex_edges(&self, row: isize, column: isize) -> Result<[usize; 6], BoardError> {
+        let (row, column) = self.check_hex(row, column)?;
+        Ok(self.get_hex_edges(row, column))
+    }
+
+    pub fn try_get_intersection_edges(&self, intersection: usize)
+        -> Result<Vec<usize>, BoardError> {
+        let intersection = self.check_intersection(intersection)?;
+        Ok(self.get_intersection_edges(intersection))
+    }
+
+    pub fn try_get_intersection_hexes(&self, intersection: usize)
+        -> Result<Vec<(usize, usize)>, BoardError> {
+        let intersection = self.check_intersection(intersection)?;
+        Ok(self.get_intersection_hexes(intersection))
+    }
+
+    pub fn try_get_intersection_neighbors(&self, intersection: usize)
+        -> Result<Vec<usize>, BoardError> {
+        let intersection = self.check_intersection(intersection)?;
+        Ok(self.get_intersection_neighbors(intersection))
+    }
+
+    pub fn try_get_edge_endpoints(&self, edge: usize) -> Result<(usize, usize), BoardError> {
+        let edge = self.check_edge(edge)?;
+        Ok(self.get_edge_endpoints(edge))
+    }
+
+    pub fn try_get_edge_hexes(&self, edge: usize) -> Result<Vec<(usize, usize)>, BoardError> {
+        let edge = self.check_edge(edge)?;
+        Ok(self.get_edge_hexes(edge))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The boards in the diagrams at the top of the file.
+    fn make_diagram_board() -> Board {
+        let mut board: Board = Default::default();
+        board.init(3, 3);
+        board
+    }
+
+    // Translate the labels in the diagrams into indices.
+    fn label(c: char) -> usize {
+        c.to_digit(32).unwrap() as usize
+    }
+
+    fn labels(s: &str) -> Vec<usize> {
+        s.split_whitespace().map(|l| label(l.chars().next().unwrap())).collect()
+    }
+
+    fn vertical(c: char) -> usize {
+        label(c)
+    }
+
+    fn right(c: char) -> usize {
+        20 + label(c)
+    }
+
+    fn left(c: char) -> usize {
+        20 + 16 + label(c)
+    }
+
+    #[test]
+    fn test_board_init() {
+        let mut board: Board = Default::default();
+        board.init(1, 1);
+        assert_eq!(6, board.vertical_edge_size);
+        assert_eq!(4, board.right_edge_size);
+    }
+
+    #[test]
+    fn test_diagram_board_size() {
+        let board = make_diagram_board();
+        assert_eq!(32, board.get_intersection_size());
+        assert_eq!(20 + 16 + 20, board.get_edge_size());
+    }
+
+    #[test]
+    fn test_get_hex_intersections() {
+        let board = make_diagram_board();
+        assert_eq!(labels("1 5 9 C 8 4"), board.get_hex_intersections(0, 0).to_vec());
+        assert_eq!(labels("3 7 B E A 6"), board.get_hex_intersections(0, 2).to_vec());
+        assert_eq!(labels("A E I L H D"), board.get_hex_intersections(1, 1).to_vec());
+        assert_eq!(labels("H L P S O K"), board.get_hex_intersections(2, 0).to_vec());
+        assert_eq!(labels("J N R U Q M"), board.get_hex_intersections(2, 2).to_vec());
+    }
+
+    #[test]
+    fn test_get_hex_edges() {
+        let board = make_diagram_board();
+        assert_eq!(
+            vec![right('1'), vertical('5'), left('6'), right('4'), vertical('4'), left('1')],
+            board.get_hex_edges(0, 0).to_vec());
+        assert_eq!(
+            vec![right('6'), vertical('A'), left('C'), right('9'), vertical('9'), left('7')],
+            board.get_hex_edges(1, 1).to_vec());
+        assert_eq!(
+            vec![right('B'), vertical('F'), left('I'), right('E'), vertical('E'), left('D')],
+            board.get_hex_edges(2, 2).to_vec());
+    }
+
+    #[test]
+    fn test_get_intersection_edges() {
+        let board = make_diagram_board();
+        // The north corner of (0, 0) has no edge going up.
+        assert_eq!(vec![right('1'), left('1')], board.get_intersection_edges(label('1')));
+        assert_eq!(vec![right('1'), left('2'), vertical('5')],
+                   board.get_intersection_edges(label('5')));
+        assert_eq!(vec![vertical('5'), right('5'), left('6')],
+                   board.get_intersection_edges(label('9')));
+        assert_eq!(vec![right('4'), left('6'), vertical('8')],
+                   board.get_intersection_edges(label('C')));
+        // The northwest corner of (0, 0) is on the border.
+        assert_eq!(vec![left('1'), vertical('4')], board.get_intersection_edges(label('4')));
+        assert_eq!(vec![vertical('F'), left('I')], board.get_intersection_edges(label('R')));
+    }
+
+    #[test]
+    fn test_get_intersection_hexes() {
+        let board = make_diagram_board();
+        assert_eq!(Vec::<(usize, usize)>::new(), board.get_intersection_hexes(label('0')));
+        assert_eq!(vec![(0, 0)], board.get_intersection_hexes(label('1')));
+        assert_eq!(vec![(0, 0), (0, 1)], board.get_intersection_hexes(label('5')));
+        assert_eq!(vec![(1, 0), (0, 0), (0, 1)], board.get_intersection_hexes(label('9')));
+        assert_eq!(vec![(1, 0), (1, 1), (0, 1)], board.get_intersection_hexes(label('D')));
+        assert_eq!(vec![(2, 2)], board.get_intersection_hexes(label('U')));
+        assert_eq!(Vec::<(usize, usize)>::new(), board.get_intersection_hexes(label('V')));
+    }
+
+    #[test]
+    fn test_get_intersection_neighbors() {
+        let board = make_diagram_board();
+        assert_eq!(labels("5 4"), board.get_intersection_neighbors(label('1')));
+        assert_eq!(labels("5 D C"), board.get_intersection_neighbors(label('9')));
+        assert_eq!(labels("9 A H"), board.get_intersection_neighbors(label('D')));
+        assert_eq!(labels("Q R"), board.get_intersection_neighbors(label('U')));
+    }
+
+    #[test]
+    fn test_get_edge_endpoints() {
+        let board = make_diagram_board();
+        assert_eq!((label('8'), label('C')), board.get_edge_endpoints(right('4')));
+        assert_eq!((label('9'), label('C')), board.get_edge_endpoints(left('6')));
+        assert_eq!((label('5'), label('9')), board.get_edge_endpoints(vertical('5')));
+        assert_eq!((label('C'), label('G')), board.get_edge_endpoints(vertical('8')));
+        assert_eq!((label('R'), label('U')), board.get_edge_endpoints(left('I')));
+    }
+
+    #[test]
+    fn test_get_edge_hexes() {
+        let board = make_diagram_board();
+        assert_eq!(vec![(0, 0)], board.get_edge_hexes(vertical('4')));
+        assert_eq!(vec![(0, 0), (0, 1)], board.get_edge_hexes(vertical('5')));
+        assert_eq!(vec![(1, 0), (0, 0)], board.get_edge_hexes(left('6')));
+        assert_eq!(vec![(1, 0), (0, 1)], board.get_edge_hexes(right('5')));
+        assert_eq!(vec![(0, 0)], board.get_edge_hexes(right('1')));
+        assert_eq!(Vec::<(usize, usize)>::new(), board.get_edge_hexes(vertical('0')));
+        assert_eq!(Vec::<(usize, usize)>::new(), board.get_edge_hexes(left('0')));
+        assert_eq!(Vec::<(usize, usize)>::new(), board.get_edge_hexes(right('0')));
+    }
+
+    #[test]
+    fn test_every_hex_side_joins_two_corners() {
+        let board = make_diagram_board();
+        for row in 0..3 {
+            for column in 0..3 {
+                let corners = board.get_hex_intersections(row, column);
+                let sides = board.get_hex_edges(row, column);
+                for i in 0..6 {
+                    let (a, b) = board.get_edge_endpoints(sides[i]);
+                    let (c, d) = (corners[i], corners[(i + 1) % 6]);
+                    assert!((a, b) == (c, d) || (a, b) == (d, c));
+                    assert!(board.get_edge_hexes(sides[i]).contains(&(row, column)));
+                    assert!(board.get_intersection_hexes(corners[i]).contains(&(row, column)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_get_hex_neighbor() {
+        let board = make_diagram_board();
+        assert_eq!(Ok(Some((0, 1))), board.get_hex_neighbor(1, 0, HexDirection::Northeast));
+        assert_eq!(Ok(Some((1, 1))), board.get_hex_neighbor(1, 0, HexDirection::East));
+        assert_eq!(Ok(Some((2, 0))), board.get_hex_neighbor(1, 0, HexDirection::Southeast));
+        assert_eq!(Ok(None), board.get_hex_neighbor(1, 0, HexDirection::Southwest));
+        assert_eq!(Ok(None), board.get_hex_neighbor(1, 0, HexDirection::West));
+        assert_eq!(Ok(Some((0, 0))), board.get_hex_neighbor(1, 0, HexDirection::Northwest));
+        assert_eq!(Err(BoardError::HexOffBoard(-1, 0)),
+                   board.get_hex_neighbor(-1, 0, HexDirection::Southeast));
+        assert_eq!(Err(BoardError::HexOffBoard(0, 3)),
+                   board.get_hex_neighbor(0, 3, HexDirection::West));
+    }
+
+    #[test]
+    fn test_hex_neighbors_share_a_side() {
+        let board = make_diagram_board();
+        for row in 0..3 {
+            for column in 0..3 {
+                let edges = board.get_hex_edges(row, column);
+                for direction in &HEX_DIRECTIONS {
+                    let neighbor = board.get_hex_neighbor(row as isize, column as isize,
+                                                          *direction).unwrap();
+                    let side = edges[direction.get_side()];
+                    let mut expected = vec![(row, column)];
+                    if let Some(neighbor) = neighbor {
+                        let opposite = direction.opposite().get_side();
+                        assert_eq!(side, board.get_hex_edges(neighbor.0, neighbor.1)[opposite]);
+                        expected.push(neighbor);
+                    }
+                    let mut hexes = board.get_edge_hexes(side);
+                    hexes.sort();
+                    expected.sort();
+                    assert_eq!(expected, hexes);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_get_hex_neighbors() {
+        let board = make_diagram_board();
+        assert_eq!(Ok(vec![(0, 1), (1, 0)]), board.get_hex_neighbors(0, 0));
+        assert_eq!(Ok(vec![(0, 2), (1, 2), (2, 1), (2, 0), (1, 0), (0, 1)]),
+                   board.get_hex_neighbors(1, 1));
+        assert!(board.get_hex_neighbors(3, 3).is_err());
+    }
+
+    #[test]
+    fn test_checked_lookups() {
+        let board = make_diagram_board();
+        assert_eq!(Err(BoardError::HexOffBoard(0, -1)), board.try_get_hex_intersections(0, -1));
+        assert_eq!(Err(BoardError::HexOffBoard(3, 0)), board.try_get_hex_edges(3, 0));
+        assert_eq!(Ok(board.get_hex_edges(2, 2)), board.try_get_hex_edges(2, 2));
+        assert_eq!(Err(BoardError::IntersectionOffBoard(label('0'))),
+                   board.try_get_intersection_edges(label('0')));
+        assert_eq!(Err(BoardError::IntersectionOffBoard(32)),
+                   board.try_get_intersection_hexes(32));
+        assert_eq!(Ok(labels("5 D C")), board.try_get_intersection_neighbors(label('9')));
+        assert_eq!(Err(BoardError::EdgeOffBoard(left('0'))),
+                   board.try_get_edge_endpoints(left('0')));
+        assert_eq!(Err(BoardError::EdgeOffBoard(56)), board.try_get_edge_hexes(56));
+        assert_eq!(Ok((label('5'), label('9'))), board.try_get_edge_endpoints(vertical('5')));
+    }
+
+    #[test]
+    fn test_standard_board() {
+        let mut board: Board = Default::default();
+        board.init_shape(&Shape::standard());
+        assert_eq!(37, board.get_hexes().len());
+        assert_eq!(19, board.get_land_hexes().len());
+        let land_intersections = board.get_intersections().iter()
+            .filter(|&&i| board.get_intersection_kind(i) == IntersectionKind::Land)
+            .count();
+        assert_eq!(54, land_intersections);
+        let edges = board.get_edges();
+        let land_edges = edges.iter()
+            .filter(|&&e| board.get_edge_kind(e) == EdgeKind::Land)
+            .count();
+        let coast_edges = edges.iter()
+            .filter(|&&e| board.get_edge_kind(e) == EdgeKind::Coast)
+            .count();
+        assert_eq!(42, land_edges);
+        assert_eq!(30, coast_edges);
+        assert_eq!(None, board.get_hex(0, 0));
+        assert_eq!(Err(BoardError::HexOffBoard(6, 6)), board.try_get_hex_edges(6, 6));
+        assert_eq!(HexKind::Sea, board.get_hex_kind(0, 3));
+    }
+
+    #[test]
+    fn test_extended_board() {
+        let mut board: Board = Default::default();
+        board.init_shape(&Shape::extended());
+        let land_intersections = board.get_intersections().iter()
+            .filter(|&&i| board.get_intersection_kind(i) == IntersectionKind::Land)
+            .count();
+        let land_edges = board.get_edges().iter()
+            .filter(|&&e| board.get_edge_kind(e) != EdgeKind::Sea)
+            .count();
+        assert_eq!(30, board.get_land_hexes().len());
+        assert_eq!(80, land_intersections);
+        // The land is a disk, so V - E + F = 1.
+        assert_eq!(80 + 30 - 1, land_edges);
+    }
+
+    #[test]
+    fn test_custom_shape() {
+        let shape = Shape::parse("
+            L . L
+             S L .
+        ").unwrap();
+        let mut board: Board = Default::default();
+        board.init_shape(&shape);
+        assert_eq!(vec![(0, 0), (0, 2), (1, 0), (1, 1)], board.get_hexes());
+        assert_eq!(Ok(vec![(1, 0)]), board.get_hex_neighbors(0, 0));
+        assert_eq!(Err(BoardError::HexOffBoard(0, 1)), board.get_hex_neighbors(0, 1));
+        let edges = board.get_hex_edges(1, 0);
+        // The sides shared with (1, 1) and (0, 0) are coasts. The southwest side only has sea.
+        assert_eq!(EdgeKind::Coast, board.get_edge_kind(edges[1]));
+        assert_eq!(EdgeKind::Coast, board.get_edge_kind(edges[5]));
+        assert_eq!(EdgeKind::Sea, board.get_edge_kind(edges[3]));
+        let corners = board.get_hex_intersections(1, 0);
+        assert_eq!(IntersectionKind::Sea, board.get_intersection_kind(corners[3]));
+        assert_eq!(IntersectionKind::Land, board.get_intersection_kind(corners[0]));
+        // No hex is at (0, 1), so its sides border at most one hex.
+        assert_eq!(vec![(0, 2)], board.get_edge_hexes(board.get_hex_edges(0, 1)[1]));
+        assert_eq!(EdgeKind::Invalid, board.get_edge_kind(board.get_hex_edges(0, 1)[0]));
+    }
+
+    #[test]
+    fn test_set_terrain() {
+        let mut board: Board = Default::default();
+        board.init_shape(&Shape::standard());
+        assert_eq!(TerrainKind::Sea, board.get_tile(0, 3));
+        assert_eq!(TerrainKind::Desert, board.get_tile(3, 3));
+        let six = NumberToken::new(6);
+        board.set_terrain(3, 3, TerrainKind::Mountains, six);
+        board.set_terrain(2, 2, TerrainKind::Fields, six);
+        assert_eq!(TerrainKind::Mountains, board.get_tile(3, 3));
+        assert_eq!(six, board.get_token(3, 3));
+        assert_eq!(vec![(2, 2), (3, 3)], board.get_hexes_with_number(6));
+
+        // Flooding a hex on the coast makes its sides with the sea into sea edges.
+        let edges = board.get_hex_edges(1, 3);
+        assert_eq!(EdgeKind::Coast, board.get_edge_kind(edges[5]));
+        board.set_terrain(1, 3, TerrainKind::Sea, None);
+        assert_eq!(HexKind::Sea, board.get_hex_kind(1, 3));
+        assert_eq!(None, board.get_robber());
+        board.set_robber(3, 3);
+        assert_eq!(Some((3, 3)), board.get_robber());
+        assert_eq!(EdgeKind::Sea, board.get_edge_kind(edges[5]));
+        assert_eq!(EdgeKind::Coast, board.get_edge_kind(edges[2]));
+        assert_eq!(18, board.get_land_hexes().len());
+    }
+
+    #[test]
+    fn test_pieces() {
+        let mut board = make_diagram_board();
+        let (alice, bob) = (PlayerId(0), PlayerId(1));
+        assert_eq!(None, board.get_building(label('9')));
+        board.set_building(label('9'), Some(Building::settlement(alice)));
+        board.set_building(label('I'), Some(Building::city(alice)));
+        board.set_building(label('5'), Some(Building::settlement(bob)));
+        board.set_road(vertical('5'), Some(Road::road(bob)));
+        board.set_road(right('5'), Some(Road::road(alice)));
+        board.set_road(left('C'), Some(Road::ship(alice)));
+
+        assert_eq!(vec![label('9'), label('I')], board.get_buildings(alice));
+        assert_eq!(vec![label('5')], board.get_buildings(bob));
+        assert_eq!(vec![right('5'), left('C')], board.get_roads(alice));
+        assert_eq!(Some(bob), board.get_edge_owner(vertical('5')));
+        assert_eq!(None, board.get_edge_owner(vertical('4')));
+        assert_eq!(Some(alice), board.get_intersection_owner(label('I')));
+        assert_eq!(vec![vertical('5'), right('5')], board.get_adjacent_roads(label('9')));
+        assert_eq!(vec![(label('5'), Building::settlement(bob)),
+                        (label('9'), Building::settlement(alice))],
+                   board.get_hex_buildings(0, 0));
+
+        board.set_building(label('9'), None);
+        assert_eq!(vec![label('I')], board.get_buildings(alice));
+    }
+
+    #[test]
+    fn test_harbors() {
+        let mut board: Board = Default::default();
+        board.init_shape(&Shape::standard());
+        let alice = PlayerId(0);
+        // The northwest side of (1, 3) and the west side of (3, 1) face the sea.
+        let ore = board.get_hex_edges(1, 3)[5];
+        let generic = board.get_hex_edges(3, 1)[4];
+        board.set_harbor(ore, Some(Harbor::Special(Resource::Ore)));
+        board.set_harbor(generic, Some(Harbor::Generic));
+        assert_eq!(vec![(generic, Harbor::Generic), (ore, Harbor::Special(Resource::Ore))],
+                   board.get_harbors());
+        assert_eq!([4; 5], board.get_trade_rates(alice));
+
+        let (a, b) = board.get_edge_endpoints(ore);
+        assert_eq!(vec![Harbor::Special(Resource::Ore)], board.get_intersection_harbors(a));
+        board.set_building(b, Some(Building::settlement(alice)));
+        assert_eq!(2, board.get_trade_rate(alice, Resource::Ore));
+        assert_eq!(4, board.get_trade_rate(alice, Resource::Wool));
+        assert_eq!(4, board.get_trade_rate(PlayerId(1), Resource::Ore));
+
+        let (a, _) = board.get_edge_endpoints(generic);
+        board.set_building(a, Some(Building::city(alice)));
+        assert_eq!([3, 3, 2, 3, 3], board.get_trade_rates(alice));
+
+        // Flooding the hex leaves the harbor at sea, so it is taken away.
+        board.set_terrain(1, 3, TerrainKind::Sea, None);
+        assert_eq!(None, board.get_harbor(ore));
+    }
+}
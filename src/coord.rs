@@ -0,0 +1,303 @@
+// Coordinates for hexes.
+//
+// The board stores hexes by (row, column). These are axial coordinates with q as the column and
+// r as the row, so moving east adds one to q and moving southeast adds one to r.
+//
+// Cube coordinates add a third axis so that x + y + z = 0.
+// We use x = q and z = r, so y = -q - r.
+//
+// Offset coordinates shift every odd row half a hex to the right, like this
+//
+//           /\  /\  /\
+//          /  \/  \/  \
+//         |   ||   |   |
+//         |0,0||0,1|0,2|
+//          \  /\  /\  /\
+//           \/  \/  \/  \
+//           |   ||   |   |
+//           |1,0||1,1|1,2|
+//           /\  /\  /\  /
+//          /  \/  \/  \/
+//         |   ||   |   |
+//         |2,0||2,1|2,2|
+//          \  /\  /\  /
+//           \/  \/  \/
+//
+// This is the layout that get_vertex_from_hex used for the triangle grid.
+
+use board::{Board, HexDirection, HEX_DIRECTIONS};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Axial {
+    pub q: isize,
+    pub r: isize,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Cube {
+    pub x: isize,
+    pub y: isize,
+    pub z: isize,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Offset {
+    pub row: isize,
+    pub column: isize,
+}
+
+impl Axial {
+    pub fn new(q: isize, r: isize) -> Axial {
+        Axial { q, r }
+    }
+
+    // The axial coordinates of the hex at (row, column) on the board.
+    pub fn from_hex(row: usize, column: usize) -> Axial {
+        Axial::new(column as isize, row as isize)
+    }
+
+    // The hex on the board at these coordinates, if there is one.
+    pub fn to_hex(&self, board: &Board) -> Option<(usize, usize)> {
+        board.get_hex(self.r, self.q)
+    }
+
+    pub fn to_cube(&self) -> Cube {
+        Cube {
+            x: self.q,
+            y: -self.q - self.r,
+            z: self.r,
+        }
+    }
+
+    pub fn to_offset(&self) -> Offset {
+        Offset {
+            row: self.r,
+            column: self.q + (self.r - (self.r & 1)) / 2,
+        }
+    }
+
+    pub fn add(&self, other: Axial) -> Axial {
+        Axial::new(self.q + other.q, self.r + other.r)
+    }
+
+    pub fn subtract(&self, other: Axial) -> Axial {
+        Axial::new(self.q - other.q, self.r - other.r)
+    }
+
+    pub fn scale(&self, factor: isize) -> Axial {
+        Axial::new(self.q * factor, self.r * factor)
+    }
+
+    // The change in coordinates for one step in the direction.
+    pub fn direction(direction: HexDirection) -> Axial {
+        let (row, column) = direction.get_offset();
+        Axial::new(column, row)
+    }
+
+    pub fn neighbor(&self, direction: HexDirection) -> Axial {
+        self.add(Axial::direction(direction))
+    }
+
+    // The neighbors clockwise from the northeast.
+    pub fn neighbors(&self) -> [Axial; 6] {
+        let mut neighbors = [*self; 6];
+        for (neighbor, direction) in neighbors.iter_mut().zip(HEX_DIRECTIONS.iter()) {
+            *neighbor = self.neighbor(*direction);
+        }
+        neighbors
+    }
+
+    // The number of steps between the hexes.
+    pub fn distance(&self, other: Axial) -> usize {
+        self.to_cube().distance(other.to_cube())
+    }
+
+    // The hexes at exactly the given distance, clockwise from the west.
+    pub fn ring(&self, radius: usize) -> Vec<Axial> {
+        if radius == 0 {
+            return vec![*self];
+        }
+        let mut ring = Vec::with_capacity(6 * radius);
+        let mut hex = self.add(Axial::direction(HexDirection::West).scale(radius as isize));
+        // Walking clockwise from the west corner starts by heading northeast.
+        for direction in &HEX_DIRECTIONS {
+            for _ in 0..radius {
+                ring.push(hex);
+                hex = hex.neighbor(*direction);
+            }
+        }
+        ring
+    }
+
+    // The hexes within the given distance, ring by ring from the center outwards.
+    pub fn spiral(&self, radius: usize) -> Vec<Axial> {
+        let mut spiral = vec![];
+        for i in 0..(radius + 1) {
+            spiral.extend(self.ring(i));
+        }
+        spiral
+    }
+
+    // The hexes on the straight line between the hexes, including both ends.
+    pub fn line(&self, other: Axial) -> Vec<Axial> {
+        let distance = self.distance(other);
+        let a = self.to_cube();
+        let b = other.to_cube();
+        // Nudge the ends so that points exactly on the border of two hexes round the same way.
+        let (ax, ay, az) = (a.x as f64 + 1e-6, a.y as f64 + 1e-6, a.z as f64 - 2e-6);
+        let (bx, by, bz) = (b.x as f64 + 1e-6, b.y as f64 + 1e-6, b.z as f64 - 2e-6);
+        let steps = if distance == 0 { 1.0 } else { distance as f64 };
+        (0..(distance + 1))
+            .map(|i| {
+                let t = i as f64 / steps;
+                Cube::round(ax + (bx - ax) * t, ay + (by - ay) * t, az + (bz - az) * t).to_axial()
+            })
+            .collect()
+    }
+}
+
+impl Cube {
+    pub fn to_axial(&self) -> Axial {
+        Axial::new(self.x, self.z)
+    }
+
+    pub fn distance(&self, other: Cube) -> usize {
+        let dx = (self.x - other.x).abs();
+        let dy = (self.y - other.y).abs();
+        let dz = (self.z - other.z).abs();
+        dx.max(dy).max(dz) as usize
+    }
+
+    // Round fractional cube coordinates to the hex that contains them.
+    pub fn round(x: f64, y: f64, z: f64) -> Cube {
+        let (mut rx, mut ry, mut rz) = (x.round(), y.round(), z.round());
+        let (dx, dy, dz) = ((rx - x).abs(), (ry - y).abs(), (rz - z).abs());
+        if dx > dy && dx > dz {
+            rx = -ry - rz;
+        } else if dy > dz {
+            ry = -rx - rz;
+        } else {
+            rz = -rx - ry;
+        }
+        Cube {
+            x: rx as isize,
+            y: ry as isize,
+            z: rz as isize,
+        }
+    }
+}
+
+impl Offset {
+    pub fn new(row: isize, column: isize) -> Offset {
+        Offset { row, column }
+    }
+
+    pub fn to_axial(&self) -> Axial {
+        Axial::new(self.column - (self.row - (self.row & 1)) / 2, self.row)
+    }
+}
+
+impl From<Cube> for Axial {
+    fn from(cube: Cube) -> Axial {
+        cube.to_axial()
+    }
+}
+
+impl From<Offset> for Axial {
+    fn from(offset: Offset) -> Axial {
+        offset.to_axial()
+    }
+}
+
+impl From<Axial> for Cube {
+    fn from(axial: Axial) -> Cube {
+        axial.to_cube()
+    }
+}
+
+impl From<Axial> for Offset {
+    fn from(axial: Axial) -> Offset {
+        axial.to_offset()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shape::Shape;
+
+    #[test]
+    fn test_conversions() {
+        let hex = Axial::new(2, -3);
+        assert_eq!(Cube { x: 2, y: 1, z: -3 }, hex.to_cube());
+        assert_eq!(hex, hex.to_cube().to_axial());
+        assert_eq!(hex, hex.to_offset().to_axial());
+        // The offsets of the first hexes in the diagram at the top of the file.
+        assert_eq!(Axial::new(0, 0), Offset::new(0, 0).to_axial());
+        assert_eq!(Axial::new(0, 1), Offset::new(1, 0).to_axial());
+        assert_eq!(Axial::new(-1, 2), Offset::new(2, 0).to_axial());
+        assert_eq!(Axial::new(0, 2), Offset::new(2, 1).to_axial());
+        assert_eq!(Offset::new(-1, -2), Axial::new(-1, -1).to_offset());
+    }
+
+    #[test]
+    fn test_neighbors_match_board() {
+        let mut board: Board = Default::default();
+        board.init(3, 3);
+        let center = Axial::from_hex(1, 1);
+        let neighbors: Vec<(usize, usize)> = center.neighbors().iter()
+            .filter_map(|hex| hex.to_hex(&board))
+            .collect();
+        assert_eq!(board.get_hex_neighbors(1, 1).unwrap(), neighbors);
+    }
+
+    #[test]
+    fn test_distance() {
+        let a = Axial::new(0, 0);
+        assert_eq!(0, a.distance(a));
+        assert_eq!(1, a.distance(Axial::new(1, -1)));
+        assert_eq!(2, a.distance(Axial::new(1, 1)));
+        assert_eq!(3, a.distance(Axial::new(-3, 2)));
+        assert_eq!(3, Axial::new(-3, 2).distance(a));
+    }
+
+    #[test]
+    fn test_ring() {
+        let center = Axial::new(0, 0);
+        assert_eq!(vec![center], center.ring(0));
+        assert_eq!(
+            vec![Axial::new(-1, 0), Axial::new(0, -1), Axial::new(1, -1),
+                 Axial::new(1, 0), Axial::new(0, 1), Axial::new(-1, 1)],
+            center.ring(1));
+        let ring = center.ring(3);
+        assert_eq!(18, ring.len());
+        assert!(ring.iter().all(|hex| hex.distance(center) == 3));
+    }
+
+    #[test]
+    fn test_spiral_covers_standard_island() {
+        let mut board: Board = Default::default();
+        board.init_shape(&Shape::standard());
+        let center = Axial::from_hex(3, 3);
+        let mut island: Vec<(usize, usize)> = center.spiral(2).iter()
+            .filter_map(|hex| hex.to_hex(&board))
+            .collect();
+        island.sort();
+        assert_eq!(board.get_land_hexes(), island);
+        assert_eq!(37, center.spiral(3).len());
+    }
+
+    #[test]
+    fn test_line() {
+        let a = Axial::new(0, 0);
+        assert_eq!(vec![a], a.line(a));
+        assert_eq!(vec![a, Axial::new(1, 0), Axial::new(2, 0), Axial::new(3, 0)],
+                   a.line(Axial::new(3, 0)));
+        let line = a.line(Axial::new(2, -4));
+        assert_eq!(5, line.len());
+        for pair in line.windows(2) {
+            assert_eq!(1, pair[0].distance(pair[1]));
+        }
+        assert_eq!(Axial::new(2, -4), *line.last().unwrap());
+    }
+}
@@ -0,0 +1,101 @@
+// Development cards, bought from a shuffled deck for ore, grain and wool.
+
+use std::fmt;
+
+use hand::Hand;
+use random::{shuffle, Randomness};
+
+pub const DEV_CARD_COST: Hand = Hand([0, 0, 1, 1, 1]);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DevCard {
+    // Move the robber and steal, like on a 7.
+    Knight,
+    // A hidden victory point.
+    VictoryPoint,
+    // Build two roads for free.
+    RoadBuilding,
+    // Take any two cards from the bank.
+    YearOfPlenty,
+    // Take every card of one resource from the other players.
+    Monopoly,
+}
+
+// The cards in a deck, and how many of each.
+pub const DECK: [(DevCard, usize); 5] = [
+    (DevCard::Knight, 14),
+    (DevCard::VictoryPoint, 5),
+    (DevCard::RoadBuilding, 2),
+    (DevCard::YearOfPlenty, 2),
+    (DevCard::Monopoly, 2),
+];
+
+impl fmt::Display for DevCard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            DevCard::Knight => "knight",
+            DevCard::VictoryPoint => "victory point",
+            DevCard::RoadBuilding => "road building",
+            DevCard::YearOfPlenty => "year of plenty",
+            DevCard::Monopoly => "monopoly",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Deck {
+    // The top of the deck is the end.
+    cards: Vec<DevCard>,
+}
+
+impl Deck {
+    // The full deck, shuffled.
+    pub fn new(random: &mut dyn Randomness) -> Deck {
+        let mut cards = vec![];
+        for &(card, count) in &DECK {
+            cards.extend(vec![card; count]);
+        }
+        shuffle(random, &mut cards);
+        Deck { cards }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cards.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cards.is_empty()
+    }
+
+    pub fn draw(&mut self) -> Option<DevCard> {
+        self.cards.pop()
+    }
+
+    // Put a drawn card back on top, to undo drawing it.
+    pub fn put_back(&mut self, card: DevCard) {
+        self.cards.push(card);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use random::Random;
+
+    #[test]
+    fn test_deck() {
+        let mut deck = Deck::new(&mut Random::new(0));
+        assert_eq!(25, deck.len());
+        assert_eq!(deck, Deck::new(&mut Random::new(0)));
+        assert!(deck != Deck::new(&mut Random::new(1)));
+        let mut knights = 0;
+        while let Some(card) = deck.draw() {
+            if card == DevCard::Knight {
+                knights += 1;
+            }
+        }
+        assert_eq!(14, knights);
+        assert!(deck.is_empty());
+    }
+}
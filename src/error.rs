@@ -0,0 +1,144 @@
+// The reasons the game refuses a move.
+
+use std::error::Error;
+use std::fmt;
+
+use board::BoardError;
+use devcard::DevCard;
+use game::Phase;
+use hand::Hand;
+use piece::{PieceKind, PlayerId};
+use terrain::Resource;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GameError {
+    GameOver,
+    // The move was made by someone other than the given player, whose turn it is.
+    NotYourTurn(PlayerId),
+    // The move can't be made in the given phase, which is the phase the game is in.
+    WrongPhase(Phase),
+    Board(BoardError),
+    // Settlements, cities and roads need land next to them, and so does the robber.
+    NotOnLand,
+    // There is already a piece there.
+    Occupied,
+    // A settlement can't go next to the building on the given intersection.
+    DistanceRule(usize),
+    // The piece isn't connected to the player's roads or buildings.
+    NotConnected,
+    // Only the player's own settlements can become cities.
+    NotYourSettlement,
+    // The robber has to move to a different hex.
+    RobberMustMove,
+    // The player is short of the given cards.
+    InsufficientResources(Hand),
+    // The player has no more of the piece.
+    NoPiecesLeft(PieceKind),
+    // None of the empty edges connect to the player's roads or buildings.
+    NoRoadSite,
+    // The player doesn't have to discard.
+    NothingToDiscard,
+    // The player has to discard exactly the given number of cards.
+    WrongDiscardCount(u32),
+    // The robber can't steal from the given player there.
+    InvalidVictim(PlayerId),
+    // Someone on the robber's hex has to be stolen from.
+    VictimRequired,
+    // The development deck is empty.
+    NoDevCards,
+    // The player has none of the card to play.
+    NoDevCard(DevCard),
+    // The player's only card of that kind was bought this turn.
+    BoughtThisTurn(DevCard),
+    // Only one development card can be played each turn.
+    AlreadyPlayedDevCard,
+    // The bank is short of the given cards.
+    BankShort(Hand),
+    // There is no player with the id.
+    NoSuchPlayer(PlayerId),
+    // Nobody has offered a trade.
+    NoTradeOffer,
+    // A trade has to swap some cards for other cards.
+    InvalidTrade,
+    // The player can't respond to their own offer.
+    OwnTrade,
+    // None of the other players has the cards the offer asks for.
+    NobodyCanAccept,
+    // The given player hasn't accepted or countered the offer.
+    NotAccepted(PlayerId),
+    // The bank takes the resource in lots of the given size.
+    TradeRate(Resource, u32),
+    // The cards given to the bank are worth the given number of cards.
+    WrongLots(u32),
+    NothingToUndo,
+    NothingToRedo,
+    // The rules don't allow taking back the last action.
+    UndoBlocked,
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            GameError::GameOver => write!(f, "the game is over"),
+            GameError::NotYourTurn(player) => write!(f, "it is {}'s turn", player),
+            GameError::WrongPhase(phase) => write!(f, "that can't be done during {}", phase),
+            GameError::Board(ref error) => write!(f, "{}", error),
+            GameError::NotOnLand => write!(f, "that has to be next to land"),
+            GameError::Occupied => write!(f, "there is already a piece there"),
+            GameError::DistanceRule(intersection) =>
+                write!(f, "too close to the building on intersection {}", intersection),
+            GameError::NotConnected => write!(f, "that isn't connected to your roads"),
+            GameError::NotYourSettlement => write!(f, "that isn't one of your settlements"),
+            GameError::RobberMustMove => write!(f, "the robber has to move to another hex"),
+            GameError::InsufficientResources(ref missing) => write!(f, "{} short", missing),
+            GameError::NoPiecesLeft(piece) => write!(f, "no {} pieces left", piece),
+            GameError::NoRoadSite => write!(f, "there is nowhere to put a road"),
+            GameError::NothingToDiscard => write!(f, "you don't have to discard"),
+            GameError::WrongDiscardCount(count) => write!(f, "you have to discard {} cards", count),
+            GameError::InvalidVictim(player) => write!(f, "the robber can't steal from {}", player),
+            GameError::VictimRequired => write!(f, "you have to pick someone to steal from"),
+            GameError::NoDevCards => write!(f, "there are no development cards left"),
+            GameError::NoDevCard(card) => write!(f, "you have no {} card", card),
+            GameError::BoughtThisTurn(card) =>
+                write!(f, "the {} card was bought this turn", card),
+            GameError::AlreadyPlayedDevCard =>
+                write!(f, "you already played a development card this turn"),
+            GameError::BankShort(ref missing) => write!(f, "the bank is {} short", missing),
+            GameError::NoSuchPlayer(player) => write!(f, "there is no {}", player),
+            GameError::NoTradeOffer => write!(f, "no trade has been offered"),
+            GameError::InvalidTrade =>
+                write!(f, "a trade has to swap some cards for different cards"),
+            GameError::OwnTrade => write!(f, "you can't respond to your own offer"),
+            GameError::NobodyCanAccept => write!(f, "nobody has the cards to accept that"),
+            GameError::NotAccepted(player) => write!(f, "{} hasn't agreed to the trade", player),
+            GameError::TradeRate(resource, rate) =>
+                write!(f, "the bank takes {} {} at a time", rate, resource),
+            GameError::WrongLots(lots) => write!(f, "that is worth {} cards from the bank", lots),
+            GameError::NothingToUndo => write!(f, "there is nothing to undo"),
+            GameError::NothingToRedo => write!(f, "there is nothing to redo"),
+            GameError::UndoBlocked => write!(f, "that can't be undone"),
+        }
+    }
+}
+
+impl Error for GameError {}
+
+impl From<BoardError> for GameError {
+    fn from(error: BoardError) -> GameError {
+        GameError::Board(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_messages() {
+        let mut missing = Hand::single(Resource::Ore, 2);
+        missing.add(Resource::Grain, 1);
+        assert_eq!("2 ore, 1 grain short", GameError::InsufficientResources(missing).to_string());
+        assert_eq!("no city pieces left", GameError::NoPiecesLeft(PieceKind::City).to_string());
+        assert_eq!("it is player 1's turn", GameError::NotYourTurn(PlayerId(1)).to_string());
+    }
+}
@@ -0,0 +1,242 @@
+// How fair a map is.
+//
+// Everything is measured in pips, the dots printed on the number tokens, since a hex with n pips
+// produces on n of the 36 rolls of two dice.
+// A fair map gives every resource about the same number of pips, doesn't pile the pips onto a
+// few intersections, doesn't put most of a resource in one corner of the island, and leaves the
+// last player to place a settlement a spot nearly as good as the first.
+
+use board::{Board, IntersectionKind};
+use terrain::{Resource, RESOURCES};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FairnessReport {
+    // The pips on the hexes producing each resource, indexed by Resource::index.
+    pub resource_pips: [u32; 5],
+    // The largest share of each resource's pips, in percent, found on one hex and its neighbors.
+    pub resource_concentration: [u32; 5],
+    // The most pips touching a single intersection.
+    pub max_intersection_pips: u32,
+    // Intersections and their pips, best first, picked so that no two are next to each other.
+    // These are the spots the players would settle if each took the best one left.
+    pub starting_spots: Vec<(usize, u32)>,
+}
+
+impl FairnessReport {
+    pub fn get_resource_pips(&self, resource: Resource) -> u32 {
+        self.resource_pips[resource.index()]
+    }
+
+    // The gap between the resources with the most and the fewest pips.
+    pub fn get_resource_spread(&self) -> u32 {
+        let most = self.resource_pips.iter().max().cloned().unwrap_or(0);
+        let fewest = self.resource_pips.iter().min().cloned().unwrap_or(0);
+        most - fewest
+    }
+
+    pub fn get_max_concentration(&self) -> u32 {
+        self.resource_concentration.iter().max().cloned().unwrap_or(0)
+    }
+
+    // The gap between the best starting spot and the last of the given number of spots.
+    // With too few spots the last one is taken to have no pips.
+    pub fn get_spot_spread(&self, spots: usize) -> u32 {
+        let best = self.starting_spots.first().map_or(0, |&(_, pips)| pips);
+        let worst = match spots.checked_sub(1) {
+            Some(last) => self.starting_spots.get(last).map_or(0, |&(_, pips)| pips),
+            None => best,
+        };
+        best - worst
+    }
+
+    // How far the report is outside the thresholds, adding up the amount by which each measure
+    // is over. Zero means the map is within them.
+    pub fn get_excess(&self, thresholds: &FairnessThresholds) -> u32 {
+        let over = |value: u32, limit: u32| value.saturating_sub(limit);
+        over(self.get_resource_spread(), thresholds.max_resource_spread) +
+            over(self.max_intersection_pips, thresholds.max_intersection_pips) +
+            over(self.get_max_concentration(), thresholds.max_concentration) +
+            over(self.get_spot_spread(thresholds.players * 2), thresholds.max_spot_spread)
+    }
+
+    pub fn is_within(&self, thresholds: &FairnessThresholds) -> bool {
+        self.get_excess(thresholds) == 0
+    }
+}
+
+// The limits a map has to stay within to count as fair.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FairnessThresholds {
+    // The most the pips of two resources can differ by.
+    pub max_resource_spread: u32,
+    // The most pips that can touch one intersection.
+    pub max_intersection_pips: u32,
+    // The largest share of a resource, in percent, that can sit on one hex and its neighbors.
+    pub max_concentration: u32,
+    // The number of players, who each get two starting spots.
+    pub players: usize,
+    // The most the best and the worst starting spot can differ by.
+    pub max_spot_spread: u32,
+}
+
+impl Default for FairnessThresholds {
+    // Limits that most random base game maps fail, but that are easy to meet by reshuffling.
+    fn default() -> FairnessThresholds {
+        FairnessThresholds {
+            max_resource_spread: 6,
+            max_intersection_pips: 13,
+            max_concentration: 80,
+            players: 4,
+            max_spot_spread: 5,
+        }
+    }
+}
+
+// The pips on the hex, if it has a number token.
+fn get_hex_pips(board: &Board, row: usize, column: usize) -> u32 {
+    board.get_token(row, column).map_or(0, |token| token.pips())
+}
+
+// The pips on the hexes around the intersection.
+pub fn get_intersection_pips(board: &Board, intersection: usize) -> u32 {
+    board.get_intersection_hexes(intersection)
+        .into_iter()
+        .map(|(row, column)| get_hex_pips(board, row, column))
+        .sum()
+}
+
+pub fn analyze(board: &Board) -> FairnessReport {
+    let land = board.get_land_hexes();
+    let mut resource_pips = [0; 5];
+    for &(row, column) in &land {
+        if let Some(resource) = board.get_tile(row, column).resource() {
+            resource_pips[resource.index()] += get_hex_pips(board, row, column);
+        }
+    }
+
+    let mut resource_concentration = [0; 5];
+    for &(row, column) in &land {
+        let mut region = vec![(row, column)];
+        region.extend(board.get_hex_neighbors(row as isize, column as isize)
+            .expect("land hexes are on the board"));
+        for resource in &RESOURCES {
+            let total = resource_pips[resource.index()];
+            if total == 0 {
+                continue;
+            }
+            let pips: u32 = region.iter()
+                .filter(|&&(row, column)| board.get_tile(row, column).resource() == Some(*resource))
+                .map(|&(row, column)| get_hex_pips(board, row, column))
+                .sum();
+            let share = &mut resource_concentration[resource.index()];
+            *share = (*share).max(pips * 100 / total);
+        }
+    }
+
+    let mut spots: Vec<(usize, u32)> = board.get_intersections()
+        .into_iter()
+        .filter(|&i| board.get_intersection_kind(i) == IntersectionKind::Land)
+        .map(|i| (i, get_intersection_pips(board, i)))
+        .collect();
+    let max_intersection_pips = spots.iter().map(|&(_, pips)| pips).max().unwrap_or(0);
+    // Best first, and lower indices first among equals so that the picks are reproducible.
+    spots.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    let mut taken = vec![false; board.get_intersection_size()];
+    let mut starting_spots = vec![];
+    for (intersection, pips) in spots {
+        if taken[intersection] {
+            continue;
+        }
+        taken[intersection] = true;
+        for neighbor in board.get_intersection_neighbors(intersection) {
+            taken[neighbor] = true;
+        }
+        starting_spots.push((intersection, pips));
+    }
+
+    FairnessReport {
+        resource_pips,
+        resource_concentration,
+        max_intersection_pips,
+        starting_spots,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shape::Shape;
+    use terrain::{NumberToken, TerrainKind};
+
+    // A strip of three land hexes with the given tiles and numbers.
+    fn make_strip(tiles: [(TerrainKind, Option<u8>); 3]) -> Board {
+        let mut board: Board = Default::default();
+        board.init_shape(&Shape::parse("S S S S S\n S L L L S\n  S S S S S").unwrap());
+        for (column, &(tile, number)) in tiles.iter().enumerate() {
+            board.set_terrain(1, column + 1, tile, number.and_then(NumberToken::new));
+        }
+        board
+    }
+
+    #[test]
+    fn test_resource_pips_and_concentration() {
+        let board = make_strip([
+            (TerrainKind::Hills, Some(6)),
+            (TerrainKind::Hills, Some(2)),
+            (TerrainKind::Forest, Some(9)),
+        ]);
+        let report = analyze(&board);
+        assert_eq!(6, report.get_resource_pips(Resource::Brick));
+        assert_eq!(4, report.get_resource_pips(Resource::Lumber));
+        assert_eq!(0, report.get_resource_pips(Resource::Ore));
+        assert_eq!(6, report.get_resource_spread());
+        assert_eq!(100, report.resource_concentration[Resource::Brick.index()]);
+        assert_eq!(0, report.resource_concentration[Resource::Ore.index()]);
+    }
+
+    #[test]
+    fn test_intersections_and_spots() {
+        let board = make_strip([
+            (TerrainKind::Hills, Some(6)),
+            (TerrainKind::Desert, None),
+            (TerrainKind::Forest, Some(8)),
+        ]);
+        let report = analyze(&board);
+        // No intersection touches both numbered hexes, so the best spots have 5 pips.
+        assert_eq!(5, report.max_intersection_pips);
+        assert_eq!(5, report.starting_spots[0].1);
+        assert_eq!(5, report.starting_spots[1].1);
+        assert_eq!(0, report.get_spot_spread(1));
+        assert!(report.starting_spots.iter().all(|&(i, _)| {
+            board.get_intersection_kind(i) == IntersectionKind::Land
+        }));
+        for (a, _) in &report.starting_spots {
+            for (b, _) in &report.starting_spots {
+                assert!(!board.get_intersection_neighbors(*a).contains(b));
+            }
+        }
+        assert_eq!(5, report.get_spot_spread(report.starting_spots.len() + 1));
+    }
+
+    #[test]
+    fn test_excess() {
+        let report = FairnessReport {
+            resource_pips: [10, 12, 8, 11, 9],
+            resource_concentration: [50, 40, 60, 50, 70],
+            max_intersection_pips: 13,
+            starting_spots: vec![(0, 13), (5, 12), (9, 10), (11, 7)],
+        };
+        let thresholds = FairnessThresholds {
+            max_resource_spread: 4,
+            max_intersection_pips: 12,
+            max_concentration: 75,
+            players: 2,
+            max_spot_spread: 4,
+        };
+        assert_eq!(1 + 2, report.get_excess(&thresholds));
+        assert!(!report.is_within(&thresholds));
+        let thresholds = FairnessThresholds { max_intersection_pips: 13, max_spot_spread: 6,
+                                              ..thresholds };
+        assert!(report.is_within(&thresholds));
+    }
+}
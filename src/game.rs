@@ -0,0 +1,1982 @@
+// The state of a game and the order its turns go in.
+//
+// The game starts with the setup. Each player places a settlement and then a road touching it,
+// first in turn order and then in reverse, so the last player places twice in a row. The second
+// settlement collects one of each resource around it.
+// After that every turn goes
+//
+//     Roll -> Discard and MoveRobber on a 7 -> Trade -> Build -> the next player's Roll
+//
+// Resources are produced as part of the roll. On a 7 every player with more than seven cards
+// discards half of them, all at once rather than in turn order, and then the roller moves the
+// robber and steals a card from someone with a building on its new hex.
+// Once per turn, the player whose turn it is can play a development card bought on an earlier
+// turn, before or after rolling. A knight moves the robber the same way a 7 does.
+// During Trade the player can trade with the others, but the others can't trade among
+// themselves. A player can end the turn from Trade or Build.
+// The game is over as soon as the player whose turn it is has enough victory points, which can
+// be at the start of their turn if they got the longest road while someone else was playing.
+
+use std::fmt;
+
+use action::{Action, Outcome};
+use bank::Bank;
+use board::{Board, EdgeKind, IntersectionKind, LayoutError};
+use devcard::{Deck, DevCard, DEV_CARD_COST};
+use error::GameError;
+use hand::{Hand, CITY_COST, ROAD_COST, SETTLEMENT_COST};
+use history::{History, Record, Revert};
+use longest_road::{award_longest_road, get_road_length};
+use piece::{Building, PieceKind, PlayerId, Road};
+use production::{produce, ProductionReport};
+use random::{Random, RandomSource, Randomness};
+use rules::Rules;
+use scoring::{award_largest_army, get_score, Score};
+use terrain::{Resource, TerrainKind, RESOURCES};
+use trade::{count_lots, get_lots, Negotiation, Offer, Response};
+
+// The pieces each player starts with.
+pub const SETTLEMENTS: u32 = 5;
+pub const CITIES: u32 = 4;
+pub const ROADS: u32 = 15;
+
+// Players holding more cards than this discard half of them on a 7.
+pub const DISCARD_LIMIT: u32 = 7;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Phase {
+    SetupSettlement,
+    // The road has to touch the settlement just placed on the intersection.
+    SetupRoad(usize),
+    Roll,
+    // Players with too many cards discard after a 7.
+    Discard,
+    MoveRobber,
+    // The given number of roads are left to place for free after playing road building.
+    FreeRoads(u32),
+    Trade,
+    Build,
+    GameOver(PlayerId),
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Phase::SetupSettlement => write!(f, "the setup settlement"),
+            Phase::SetupRoad(_) => write!(f, "the setup road"),
+            Phase::Roll => write!(f, "the roll"),
+            Phase::Discard => write!(f, "discarding"),
+            Phase::MoveRobber => write!(f, "the robber's move"),
+            Phase::FreeRoads(_) => write!(f, "road building"),
+            Phase::Trade => write!(f, "trading"),
+            Phase::Build => write!(f, "building"),
+            Phase::GameOver(winner) => write!(f, "the end of the game won by {}", winner),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Player {
+    pub hand: Hand,
+    // The pieces the player has left to build with.
+    pub settlements: u32,
+    pub cities: u32,
+    pub roads: u32,
+    // The development cards the player can play, and the ones bought this turn.
+    pub dev_cards: Vec<DevCard>,
+    pub new_dev_cards: Vec<DevCard>,
+    // The number of knights played.
+    pub knights: u32,
+}
+
+impl Default for Player {
+    fn default() -> Player {
+        Player {
+            hand: Default::default(),
+            settlements: SETTLEMENTS,
+            cities: CITIES,
+            roads: ROADS,
+            dev_cards: vec![],
+            new_dev_cards: vec![],
+            knights: 0,
+        }
+    }
+}
+
+// A saved state leaves out the history, and loads with an empty one.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameState {
+    rules: Rules,
+    board: Board,
+    players: Vec<Player>,
+    current: PlayerId,
+    phase: Phase,
+    // The number of turns taken. Each player's placement during the setup counts as a turn.
+    turn: usize,
+    last_roll: Option<u8>,
+    // The number of cards each player still has to discard.
+    discards: Vec<u32>,
+    // The phase to go back to once the robber has moved or the free roads are built.
+    resume: Phase,
+    played_dev_card: bool,
+    longest_road: Option<PlayerId>,
+    largest_army: Option<PlayerId>,
+    // The trade offered by the player whose turn it is.
+    negotiation: Option<Negotiation>,
+    bank: Bank,
+    deck: Deck,
+    random: RandomSource,
+    #[serde(skip)]
+    history: History,
+}
+
+impl GameState {
+    // Start a game on the board with the usual rules.
+    pub fn new(board: Board, players: usize, seed: u64) -> GameState {
+        GameState::with_rules(board, players, seed, Default::default())
+    }
+
+    // Start a game on the board with the randomness seeded by the seed.
+    pub fn with_rules(board: Board, players: usize, seed: u64, rules: Rules) -> GameState {
+        GameState::with_randomness(board, players, rules, Box::new(Random::new(seed)))
+    }
+
+    // Start a game on the board, with the deck, the dice and the steals coming from the
+    // randomness. The robber starts on the desert, or on the first land hex if there is no
+    // desert.
+    pub fn with_randomness(mut board: Board, players: usize, rules: Rules,
+                           mut random: Box<dyn Randomness>) -> GameState {
+        assert!(0 < players);
+        let land = board.get_land_hexes();
+        let desert = land.iter()
+            .cloned()
+            .find(|&(row, column)| board.get_tile(row, column) == TerrainKind::Desert);
+        if let Some((row, column)) = desert.or_else(|| land.first().cloned()) {
+            board.set_robber(row, column);
+        }
+        let deck = Deck::new(&mut *random);
+        let random = RandomSource::new(random);
+        GameState {
+            rules,
+            board,
+            players: vec![Default::default(); players],
+            current: PlayerId(0),
+            phase: Phase::SetupSettlement,
+            turn: 0,
+            last_roll: None,
+            discards: vec![0; players],
+            resume: Phase::Trade,
+            played_dev_card: false,
+            longest_road: None,
+            largest_army: None,
+            negotiation: None,
+            bank: Default::default(),
+            deck,
+            random,
+            history: Default::default(),
+        }
+    }
+
+    pub fn get_rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    pub fn get_history(&self) -> &History {
+        &self.history
+    }
+
+    // Check that everything kept per player fits the number of players, for a loaded state,
+    // and that every piece on the board belongs to one of them. The board checks its own
+    // layout as it loads.
+    pub fn check_layout(&self) -> Result<(), LayoutError> {
+        let players = self.players.len();
+        if players == 0 {
+            return Err(LayoutError::NoPlayers);
+        }
+        let mut sizes = vec![("discards", self.discards.len())];
+        if let Some(ref negotiation) = self.negotiation {
+            sizes.push(("trade responses", negotiation.responses.len()));
+        }
+        for (name, found) in sizes {
+            if found != players {
+                return Err(LayoutError::WrongSize { name, expected: players, found });
+            }
+        }
+        let winner = match self.phase {
+            Phase::GameOver(winner) => Some(winner),
+            _ => None,
+        };
+        let mut named = vec![Some(self.current), self.longest_road, self.largest_army, winner];
+        let board = &self.board;
+        named.extend(board.get_intersections().into_iter()
+                     .map(|intersection| board.get_building(intersection).map(|b| b.owner)));
+        named.extend(board.get_edges().into_iter()
+                     .map(|edge| board.get_road(edge).map(|road| road.owner)));
+        match named.iter().flatten().find(|player| player.0 >= players) {
+            Some(&player) => Err(LayoutError::NoSuchPlayer(player)),
+            None => Ok(()),
+        }
+    }
+
+    // A copy of the state without its history.
+    pub fn snapshot(&self) -> GameState {
+        GameState {
+            rules: self.rules,
+            board: self.board.clone(),
+            players: self.players.clone(),
+            current: self.current,
+            phase: self.phase,
+            turn: self.turn,
+            last_roll: self.last_roll,
+            discards: self.discards.clone(),
+            resume: self.resume,
+            played_dev_card: self.played_dev_card,
+            longest_road: self.longest_road,
+            largest_army: self.largest_army,
+            negotiation: self.negotiation.clone(),
+            bank: self.bank.clone(),
+            deck: self.deck.clone(),
+            random: self.random.clone(),
+            history: Default::default(),
+        }
+    }
+
+    // Record an action applied, with what undoing it needs, which GameState::apply does.
+    pub fn record(&mut self, record: Record, revert: Revert) {
+        self.history.push(record, revert);
+    }
+
+    // What undoing the action will need, worked out before the player takes it.
+    pub fn get_revert(&self, player: PlayerId, action: &Action) -> Revert {
+        let mut revert = Revert {
+            phase: self.phase,
+            resume: self.resume,
+            last_roll: self.last_roll,
+            played_dev_card: self.played_dev_card,
+            longest_road: self.longest_road,
+            largest_army: self.largest_army,
+            negotiation: self.negotiation.clone(),
+            robber: self.board.get_robber(),
+            random: None,
+            collected: Default::default(),
+            taken: vec![],
+            dev_cards: 0,
+        };
+        match *action {
+            Action::PlaceSettlement(intersection)
+                if self.phase == Phase::SetupSettlement && self.turn >= self.players.len() => {
+                revert.collected = self.get_collected(intersection);
+            }
+            Action::Roll | Action::MoveRobber { victim: Some(_), .. } => {
+                revert.random = Some(self.random.clone());
+            }
+            Action::PlayKnight => revert.dev_cards = self.find_dev_card(player, DevCard::Knight),
+            Action::PlayRoadBuilding =>
+                revert.dev_cards = self.find_dev_card(player, DevCard::RoadBuilding),
+            Action::PlayYearOfPlenty(..) =>
+                revert.dev_cards = self.find_dev_card(player, DevCard::YearOfPlenty),
+            Action::PlayMonopoly(resource) => {
+                revert.dev_cards = self.find_dev_card(player, DevCard::Monopoly);
+                revert.taken = self.players.iter()
+                    .enumerate()
+                    .map(|(index, other)| {
+                        if index == player.0 { 0 } else { other.hand.get(resource) }
+                    })
+                    .collect();
+            }
+            Action::EndTurn => revert.dev_cards = self.players[player.0].new_dev_cards.len(),
+            _ => {}
+        }
+        revert
+    }
+
+    // Check that the last action can be undone under the rules.
+    pub fn check_undo(&self) -> Result<(), GameError> {
+        match self.history.get_last() {
+            Some(record) if !self.rules.undo.allows(record) => Err(GameError::UndoBlocked),
+            Some(_) => Ok(()),
+            None => Err(GameError::NothingToUndo),
+        }
+    }
+
+    // Put the state back to before the last action.
+    pub fn undo(&mut self) -> Result<(), GameError> {
+        self.check_undo()?;
+        let (record, revert) = self.history.undo().unwrap();
+        self.revert(&record, revert);
+        Ok(())
+    }
+
+    // Take the last undone action again. Undoing it put back the random number generator, so
+    // it comes out the same as before.
+    pub fn redo(&mut self) -> Result<(), GameError> {
+        let record = match self.history.get_next() {
+            Some(record) => record.clone(),
+            None => return Err(GameError::NothingToRedo),
+        };
+        self.check(record.player, &record.action)?;
+        let revert = self.get_revert(record.player, &record.action);
+        let outcome = self.perform(record.player, &record.action)?;
+        debug_assert_eq!(record.outcome, outcome);
+        self.history.redo(revert);
+        Ok(())
+    }
+
+    // Take back everything the action in the record did.
+    fn revert(&mut self, record: &Record, revert: Revert) {
+        let player = record.player;
+        match (record.action, &record.outcome) {
+            (Action::PlaceSettlement(intersection), _) => {
+                self.board.set_building(intersection, None);
+                self.players[player.0].settlements += 1;
+                if revert.phase == Phase::SetupSettlement {
+                    self.players[player.0].hand.remove_hand(&revert.collected);
+                    self.bank.receive(&revert.collected);
+                } else {
+                    self.refund(player, &SETTLEMENT_COST);
+                }
+            }
+            (Action::PlaceRoad(edge), _) => {
+                self.board.set_road(edge, None);
+                self.players[player.0].roads += 1;
+                match revert.phase {
+                    Phase::SetupRoad(_) => {
+                        self.turn -= 1;
+                        self.current = player;
+                    }
+                    Phase::Build => self.refund(player, &ROAD_COST),
+                    _ => {}
+                }
+            }
+            (Action::BuildCity(intersection), _) => {
+                self.board.set_building(intersection, Some(Building::settlement(player)));
+                self.players[player.0].cities += 1;
+                self.players[player.0].settlements -= 1;
+                self.refund(player, &CITY_COST);
+            }
+            (Action::Roll, Outcome::Rolled(_, production)) => {
+                if let Some(report) = production {
+                    for (other, hand) in self.players.iter_mut().zip(report.received.iter()) {
+                        other.hand.remove_hand(hand);
+                        self.bank.receive(hand);
+                    }
+                }
+                self.discards = vec![0; self.players.len()];
+            }
+            (Action::Discard(ref cards), _) => {
+                self.refund(player, cards);
+                self.discards[player.0] = cards.total();
+            }
+            (Action::MoveRobber { victim, .. }, outcome) => {
+                if let (Some(victim), &Outcome::Stole(card)) = (victim, outcome) {
+                    self.players[player.0].hand.remove(card, 1);
+                    self.players[victim.0].hand.add(card, 1);
+                }
+                if let Some((row, column)) = revert.robber {
+                    self.board.set_robber(row, column);
+                }
+            }
+            (Action::BuyDevCard, &Outcome::Bought(card)) => {
+                self.players[player.0].new_dev_cards.pop();
+                self.deck.put_back(card);
+                self.refund(player, &DEV_CARD_COST);
+            }
+            (Action::PlayKnight, _) => {
+                self.players[player.0].knights -= 1;
+                self.unuse_dev_card(player, DevCard::Knight, revert.dev_cards);
+            }
+            (Action::PlayRoadBuilding, _) =>
+                self.unuse_dev_card(player, DevCard::RoadBuilding, revert.dev_cards),
+            (Action::PlayYearOfPlenty(first, second), _) => {
+                self.unuse_dev_card(player, DevCard::YearOfPlenty, revert.dev_cards);
+                let mut cards = Hand::single(first, 1);
+                cards.add(second, 1);
+                self.players[player.0].hand.remove_hand(&cards);
+                self.bank.receive(&cards);
+            }
+            (Action::PlayMonopoly(resource), &Outcome::Took(taken)) => {
+                self.unuse_dev_card(player, DevCard::Monopoly, revert.dev_cards);
+                self.players[player.0].hand.remove(resource, taken);
+                for (other, &count) in self.players.iter_mut().zip(revert.taken.iter()) {
+                    other.hand.add(resource, count);
+                }
+            }
+            (Action::ConfirmTrade(partner), _) => {
+                let offer = revert.negotiation.as_ref()
+                    .and_then(|negotiation| negotiation.get_agreed(partner.0))
+                    .unwrap();
+                self.players[player.0].hand.remove_hand(&offer.get);
+                self.players[partner.0].hand.remove_hand(&offer.give);
+                self.players[player.0].hand.add_hand(&offer.give);
+                self.players[partner.0].hand.add_hand(&offer.get);
+            }
+            (Action::BankTrade(ref offer), _) => {
+                self.players[player.0].hand.remove_hand(&offer.get);
+                self.bank.receive(&offer.get);
+                self.refund(player, &offer.give);
+            }
+            (Action::EndTurn, _) => {
+                self.turn -= 1;
+                self.current = player;
+                let current = &mut self.players[player.0];
+                let playable = current.dev_cards.len() - revert.dev_cards;
+                current.new_dev_cards = current.dev_cards.split_off(playable);
+            }
+            // The rest only change the negotiation and the phase.
+            _ => {}
+        }
+        self.phase = revert.phase;
+        self.resume = revert.resume;
+        self.last_roll = revert.last_roll;
+        self.played_dev_card = revert.played_dev_card;
+        self.longest_road = revert.longest_road;
+        self.largest_army = revert.largest_army;
+        self.negotiation = revert.negotiation;
+        if let Some(random) = revert.random {
+            self.random = random;
+        }
+    }
+
+    pub fn get_board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn get_phase(&self) -> Phase {
+        self.phase
+    }
+
+    pub fn get_current_player(&self) -> PlayerId {
+        self.current
+    }
+
+    pub fn get_player(&self, player: PlayerId) -> &Player {
+        &self.players[player.0]
+    }
+
+    pub fn get_players_size(&self) -> usize {
+        self.players.len()
+    }
+
+    pub fn get_turn(&self) -> usize {
+        self.turn
+    }
+
+    pub fn get_last_roll(&self) -> Option<u8> {
+        self.last_roll
+    }
+
+    // The number of cards the player still has to discard.
+    pub fn get_discard(&self, player: PlayerId) -> u32 {
+        self.discards.get(player.0).cloned().unwrap_or(0)
+    }
+
+    pub fn get_bank(&self) -> &Bank {
+        &self.bank
+    }
+
+    pub fn get_deck(&self) -> &Deck {
+        &self.deck
+    }
+
+    // The player holding the longest road card.
+    pub fn get_longest_road(&self) -> Option<PlayerId> {
+        self.longest_road
+    }
+
+    pub fn get_negotiation(&self) -> Option<&Negotiation> {
+        self.negotiation.as_ref()
+    }
+
+    // The player holding the largest army card.
+    pub fn get_largest_army(&self) -> Option<PlayerId> {
+        self.largest_army
+    }
+
+    // Hand the longest road to whoever has it now that a road was built or broken.
+    fn update_longest_road(&mut self) {
+        let lengths: Vec<u32> = (0..self.players.len())
+            .map(|player| get_road_length(&self.board, PlayerId(player)))
+            .collect();
+        let holder = award_longest_road(self.longest_road, &lengths);
+        if holder != self.longest_road {
+            match holder {
+                Some(holder) => info!("{} has the longest road", holder),
+                None => info!("the longest road is set aside"),
+            }
+            self.longest_road = holder;
+        }
+    }
+
+    pub fn get_winner(&self) -> Option<PlayerId> {
+        match self.phase {
+            Phase::GameOver(winner) => Some(winner),
+            _ => None,
+        }
+    }
+
+    pub fn get_score(&self, player: PlayerId) -> Score {
+        get_score(&self.board, player, &self.players[player.0], self.longest_road,
+                  self.largest_army)
+    }
+
+    // The victory points of the player, including the hidden victory point cards, which can
+    // win the game on the turn they are bought.
+    pub fn get_victory_points(&self, player: PlayerId) -> u32 {
+        self.get_score(player).get_total()
+    }
+
+    // The victory points of the player the other players can see.
+    pub fn get_public_victory_points(&self, player: PlayerId) -> u32 {
+        self.get_score(player).get_public()
+    }
+
+    fn check_turn(&self, player: PlayerId) -> Result<(), GameError> {
+        if let Phase::GameOver(_) = self.phase {
+            return Err(GameError::GameOver);
+        }
+        if player != self.current {
+            return Err(GameError::NotYourTurn(self.current));
+        }
+        Ok(())
+    }
+
+    fn check_phase(&self, phase: Phase) -> Result<(), GameError> {
+        if self.phase != phase {
+            return Err(GameError::WrongPhase(self.phase));
+        }
+        Ok(())
+    }
+
+    fn check_cost(&self, player: PlayerId, cost: &Hand) -> Result<(), GameError> {
+        let shortfall = self.players[player.0].hand.get_shortfall(cost);
+        if !shortfall.is_empty() {
+            return Err(GameError::InsufficientResources(shortfall));
+        }
+        Ok(())
+    }
+
+    // Settlements go on empty land intersections with no building next to them.
+    fn check_site(&self, intersection: usize) -> Result<(), GameError> {
+        if self.board.get_intersection_kind(intersection) != IntersectionKind::Land {
+            return Err(GameError::NotOnLand);
+        }
+        if self.board.get_building(intersection).is_some() {
+            return Err(GameError::Occupied);
+        }
+        let neighbor = self.board.get_intersection_neighbors(intersection)
+            .into_iter()
+            .find(|&neighbor| self.board.get_building(neighbor).is_some());
+        match neighbor {
+            Some(neighbor) => Err(GameError::DistanceRule(neighbor)),
+            None => Ok(()),
+        }
+    }
+
+    // Whether a road of the player can continue from the intersection.
+    // A road can't continue through another player's building.
+    fn reaches(&self, player: PlayerId, intersection: usize) -> bool {
+        match self.board.get_intersection_owner(intersection) {
+            Some(owner) => owner == player,
+            None => self.board.get_adjacent_roads(intersection)
+                .into_iter()
+                .any(|edge| self.board.get_edge_owner(edge) == Some(player)),
+        }
+    }
+
+    // Roads go on empty edges with land on some side.
+    fn check_pave(&self, edge: usize) -> Result<(), GameError> {
+        let kind = self.board.get_edge_kind(edge);
+        if kind != EdgeKind::Land && kind != EdgeKind::Coast {
+            return Err(GameError::NotOnLand);
+        }
+        if self.board.get_road(edge).is_some() {
+            return Err(GameError::Occupied);
+        }
+        Ok(())
+    }
+
+    // Check that the player can place a settlement on the intersection now.
+    pub fn check_settlement(&self, player: PlayerId, intersection: usize)
+        -> Result<(), GameError> {
+        self.check_turn(player)?;
+        let setup = match self.phase {
+            Phase::SetupSettlement => true,
+            Phase::Build => false,
+            phase => return Err(GameError::WrongPhase(phase)),
+        };
+        self.board.check_intersection(intersection)?;
+        self.check_site(intersection)?;
+        let connected = self.board.get_adjacent_roads(intersection)
+            .into_iter()
+            .any(|edge| self.board.get_edge_owner(edge) == Some(player));
+        if !setup && !connected {
+            return Err(GameError::NotConnected);
+        }
+        if self.players[player.0].settlements == 0 {
+            return Err(GameError::NoPiecesLeft(PieceKind::Settlement));
+        }
+        if !setup {
+            self.check_cost(player, &SETTLEMENT_COST)?;
+        }
+        Ok(())
+    }
+
+    // Place a settlement during the setup, or build one for its price during the build phase.
+    // Built settlements have to be at the end of one of the player's roads.
+    pub fn place_settlement(&mut self, player: PlayerId, intersection: usize)
+        -> Result<(), GameError> {
+        self.check_settlement(player, intersection)?;
+        let setup = self.phase == Phase::SetupSettlement;
+        if !setup {
+            self.pay(player, &SETTLEMENT_COST);
+        }
+        self.players[player.0].settlements -= 1;
+        self.board.set_building(intersection, Some(Building::settlement(player)));
+        debug!("{} settled on intersection {}", player, intersection);
+        if setup {
+            if self.turn >= self.players.len() {
+                self.collect_around(player, intersection);
+            }
+            self.phase = Phase::SetupRoad(intersection);
+        } else {
+            // The settlement can break another player's road.
+            self.update_longest_road();
+            self.check_victory();
+        }
+        Ok(())
+    }
+
+    // Pay the bank from the player's hand.
+    fn pay(&mut self, player: PlayerId, cost: &Hand) {
+        self.players[player.0].hand.remove_hand(cost);
+        self.bank.receive(cost);
+    }
+
+    // Give back what the player paid the bank.
+    fn refund(&mut self, player: PlayerId, cost: &Hand) {
+        self.bank.pay(cost);
+        self.players[player.0].hand.add_hand(cost);
+    }
+
+    // The cards a settlement on the intersection collects, a card for each productive hex
+    // around it that the bank still has.
+    fn get_collected(&self, intersection: usize) -> Hand {
+        let mut cards: Hand = Default::default();
+        for (row, column) in self.board.get_intersection_hexes(intersection) {
+            if let Some(resource) = self.board.get_tile(row, column).resource() {
+                let mut more = cards;
+                more.add(resource, 1);
+                if self.bank.has(&more) {
+                    cards = more;
+                }
+            }
+        }
+        cards
+    }
+
+    // The second settlement of the setup collects a card for each productive hex around it.
+    fn collect_around(&mut self, player: PlayerId, intersection: usize) {
+        let cards = self.get_collected(intersection);
+        self.bank.pay(&cards);
+        self.players[player.0].hand.add_hand(&cards);
+    }
+
+    // Check that the player can place a road on the edge now.
+    pub fn check_road(&self, player: PlayerId, edge: usize) -> Result<(), GameError> {
+        self.check_turn(player)?;
+        let (settlement, free) = match self.phase {
+            Phase::SetupRoad(settlement) => (Some(settlement), true),
+            Phase::FreeRoads(_) => (None, true),
+            Phase::Build => (None, false),
+            phase => return Err(GameError::WrongPhase(phase)),
+        };
+        self.board.check_edge(edge)?;
+        self.check_pave(edge)?;
+        let (a, b) = self.board.get_edge_endpoints(edge);
+        let connected = match settlement {
+            Some(settlement) => a == settlement || b == settlement,
+            None => self.reaches(player, a) || self.reaches(player, b),
+        };
+        if !connected {
+            return Err(GameError::NotConnected);
+        }
+        if self.players[player.0].roads == 0 {
+            return Err(GameError::NoPiecesLeft(PieceKind::Road));
+        }
+        if !free {
+            self.check_cost(player, &ROAD_COST)?;
+        }
+        Ok(())
+    }
+
+    // Place the road of the setup or a free road, or build one for its price during the build
+    // phase.
+    pub fn place_road(&mut self, player: PlayerId, edge: usize) -> Result<(), GameError> {
+        self.check_road(player, edge)?;
+        if self.phase == Phase::Build {
+            self.pay(player, &ROAD_COST);
+        }
+        self.players[player.0].roads -= 1;
+        self.board.set_road(edge, Some(Road::road(player)));
+        debug!("{} built a road on edge {}", player, edge);
+        self.update_longest_road();
+        match self.phase {
+            Phase::SetupRoad(_) => self.advance_setup(),
+            Phase::FreeRoads(left) => {
+                self.place_free_roads(left - 1);
+                self.check_victory();
+            }
+            _ => self.check_victory(),
+        }
+        Ok(())
+    }
+
+    fn advance_setup(&mut self) {
+        self.turn += 1;
+        let players = self.players.len();
+        if self.turn < players {
+            self.current = PlayerId(self.turn);
+            self.phase = Phase::SetupSettlement;
+        } else if self.turn < 2 * players {
+            self.current = PlayerId(2 * players - 1 - self.turn);
+            self.phase = Phase::SetupSettlement;
+        } else {
+            self.current = PlayerId(0);
+            self.phase = Phase::Roll;
+            info!("the setup is over");
+        }
+    }
+
+    // Check that the player can upgrade the settlement on the intersection now.
+    pub fn check_city(&self, player: PlayerId, intersection: usize) -> Result<(), GameError> {
+        self.check_turn(player)?;
+        self.check_phase(Phase::Build)?;
+        self.board.check_intersection(intersection)?;
+        if self.board.get_building(intersection) != Some(Building::settlement(player)) {
+            return Err(GameError::NotYourSettlement);
+        }
+        if self.players[player.0].cities == 0 {
+            return Err(GameError::NoPiecesLeft(PieceKind::City));
+        }
+        self.check_cost(player, &CITY_COST)
+    }
+
+    // Upgrade one of the player's settlements to a city.
+    pub fn build_city(&mut self, player: PlayerId, intersection: usize) -> Result<(), GameError> {
+        self.check_city(player, intersection)?;
+        self.pay(player, &CITY_COST);
+        self.players[player.0].cities -= 1;
+        self.players[player.0].settlements += 1;
+        self.board.set_building(intersection, Some(Building::city(player)));
+        debug!("{} built a city on intersection {}", player, intersection);
+        self.check_victory();
+        Ok(())
+    }
+
+    fn check_victory(&mut self) {
+        if self.get_victory_points(self.current) >= self.rules.victory_target {
+            info!("{} won", self.current);
+            self.phase = Phase::GameOver(self.current);
+        }
+    }
+
+    // Check that the player can buy a development card now.
+    pub fn check_buy_dev_card(&self, player: PlayerId) -> Result<(), GameError> {
+        self.check_turn(player)?;
+        self.check_phase(Phase::Build)?;
+        if self.deck.is_empty() {
+            return Err(GameError::NoDevCards);
+        }
+        self.check_cost(player, &DEV_CARD_COST)
+    }
+
+    // Buy the top card of the deck. It can't be played until the next turn.
+    pub fn buy_dev_card(&mut self, player: PlayerId) -> Result<DevCard, GameError> {
+        self.check_buy_dev_card(player)?;
+        self.pay(player, &DEV_CARD_COST);
+        let card = self.deck.draw().unwrap();
+        debug!("{} bought a development card", player);
+        self.players[player.0].new_dev_cards.push(card);
+        self.check_victory();
+        Ok(card)
+    }
+
+    // Check that the player can play the card now.
+    fn check_dev_card(&self, player: PlayerId, card: DevCard) -> Result<(), GameError> {
+        self.check_turn(player)?;
+        match self.phase {
+            Phase::Roll | Phase::Trade | Phase::Build => {}
+            phase => return Err(GameError::WrongPhase(phase)),
+        }
+        if self.played_dev_card {
+            return Err(GameError::AlreadyPlayedDevCard);
+        }
+        let player = &self.players[player.0];
+        if !player.dev_cards.contains(&card) {
+            if player.new_dev_cards.contains(&card) {
+                return Err(GameError::BoughtThisTurn(card));
+            }
+            return Err(GameError::NoDevCard(card));
+        }
+        Ok(())
+    }
+
+    // Where the first of the card is among the player's development cards.
+    fn find_dev_card(&self, player: PlayerId, card: DevCard) -> usize {
+        self.players[player.0].dev_cards.iter().position(|&other| other == card).unwrap_or(0)
+    }
+
+    fn use_dev_card(&mut self, player: PlayerId, card: DevCard) {
+        let index = self.find_dev_card(player, card);
+        self.players[player.0].dev_cards.remove(index);
+        self.played_dev_card = true;
+        info!("{} played {}", player, card);
+    }
+
+    // Put the played card back where it was.
+    fn unuse_dev_card(&mut self, player: PlayerId, card: DevCard, index: usize) {
+        self.players[player.0].dev_cards.insert(index, card);
+    }
+
+    pub fn check_knight(&self, player: PlayerId) -> Result<(), GameError> {
+        self.check_dev_card(player, DevCard::Knight)
+    }
+
+    // Play a knight and move the robber, then carry on from where the turn was.
+    pub fn play_knight(&mut self, player: PlayerId) -> Result<(), GameError> {
+        self.check_knight(player)?;
+        self.use_dev_card(player, DevCard::Knight);
+        self.players[player.0].knights += 1;
+        let knights: Vec<u32> = self.players.iter().map(|player| player.knights).collect();
+        let holder = award_largest_army(self.largest_army, &knights);
+        if holder != self.largest_army {
+            info!("{} has the largest army", player);
+            self.largest_army = holder;
+        }
+        self.resume = self.phase;
+        self.phase = Phase::MoveRobber;
+        self.check_victory();
+        Ok(())
+    }
+
+    // Road building can't be played with nowhere to put a road, so the card isn't wasted.
+    pub fn check_road_building(&self, player: PlayerId) -> Result<(), GameError> {
+        self.check_dev_card(player, DevCard::RoadBuilding)?;
+        if self.players[player.0].roads == 0 {
+            return Err(GameError::NoPiecesLeft(PieceKind::Road));
+        }
+        let site = self.board.get_edges().into_iter().any(|edge| {
+            let (a, b) = self.board.get_edge_endpoints(edge);
+            self.check_pave(edge).is_ok() && (self.reaches(player, a) || self.reaches(player, b))
+        });
+        if !site {
+            return Err(GameError::NoRoadSite);
+        }
+        Ok(())
+    }
+
+    // Play road building and place up to two roads for free.
+    pub fn play_road_building(&mut self, player: PlayerId) -> Result<(), GameError> {
+        self.check_road_building(player)?;
+        self.use_dev_card(player, DevCard::RoadBuilding);
+        self.resume = self.phase;
+        self.place_free_roads(2);
+        Ok(())
+    }
+
+    // Go on placing free roads, or go back once there are none left or none can be placed.
+    fn place_free_roads(&mut self, left: u32) {
+        self.phase = Phase::FreeRoads(left);
+        let player = self.current;
+        let stuck = self.board.get_edges()
+            .into_iter()
+            .all(|edge| self.check_road(player, edge).is_err());
+        if left == 0 || stuck {
+            self.phase = self.resume;
+        }
+    }
+
+    pub fn check_year_of_plenty(&self, player: PlayerId, first: Resource, second: Resource)
+        -> Result<(), GameError> {
+        self.check_dev_card(player, DevCard::YearOfPlenty)?;
+        let mut cards = Hand::single(first, 1);
+        cards.add(second, 1);
+        let shortfall = self.bank.get_cards().get_shortfall(&cards);
+        if !shortfall.is_empty() {
+            return Err(GameError::BankShort(shortfall));
+        }
+        Ok(())
+    }
+
+    // Play year of plenty and take the two cards, which can be the same, from the bank.
+    pub fn play_year_of_plenty(&mut self, player: PlayerId, first: Resource, second: Resource)
+        -> Result<(), GameError> {
+        self.check_year_of_plenty(player, first, second)?;
+        self.use_dev_card(player, DevCard::YearOfPlenty);
+        let mut cards = Hand::single(first, 1);
+        cards.add(second, 1);
+        self.bank.pay(&cards);
+        self.players[player.0].hand.add_hand(&cards);
+        Ok(())
+    }
+
+    pub fn check_monopoly(&self, player: PlayerId) -> Result<(), GameError> {
+        self.check_dev_card(player, DevCard::Monopoly)
+    }
+
+    // Play monopoly and take every card of the resource from the other players.
+    // Returns the number of cards taken.
+    pub fn play_monopoly(&mut self, player: PlayerId, resource: Resource)
+        -> Result<u32, GameError> {
+        self.check_monopoly(player)?;
+        self.use_dev_card(player, DevCard::Monopoly);
+        let mut taken = 0;
+        for (index, other) in self.players.iter_mut().enumerate() {
+            if index == player.0 {
+                continue;
+            }
+            let count = other.hand.get(resource);
+            other.hand.remove(resource, count);
+            taken += count;
+        }
+        self.players[player.0].hand.add(resource, taken);
+        Ok(taken)
+    }
+
+    pub fn check_roll(&self, player: PlayerId) -> Result<(), GameError> {
+        self.check_turn(player)?;
+        self.check_phase(Phase::Roll)
+    }
+
+    // Roll the dice, and give out resources or have the player move the robber.
+    // Returns the roll and what it produced, which is nothing on a 7.
+    pub fn roll(&mut self, player: PlayerId)
+        -> Result<(u8, Option<ProductionReport>), GameError> {
+        self.check_roll(player)?;
+        let roll = self.random.roll_dice();
+        Ok((roll, self.resolve_roll(roll)))
+    }
+
+    fn resolve_roll(&mut self, roll: u8) -> Option<ProductionReport> {
+        info!("{} rolled {}", self.current, roll);
+        self.last_roll = Some(roll);
+        if roll == 7 {
+            self.discards = self.players.iter()
+                .map(|player| {
+                    let cards = player.hand.total();
+                    if cards > DISCARD_LIMIT { cards / 2 } else { 0 }
+                })
+                .collect();
+            self.resume = Phase::Trade;
+            self.phase = if self.discards.iter().any(|&count| count > 0) {
+                Phase::Discard
+            } else {
+                Phase::MoveRobber
+            };
+            return None;
+        }
+        let report = produce(&self.board, &mut self.bank, roll, self.players.len());
+        for (player, hand) in self.players.iter_mut().zip(report.received.iter()) {
+            player.hand.add_hand(hand);
+        }
+        for resource in &report.shortages {
+            info!("the bank ran out of {}", resource);
+        }
+        self.phase = Phase::Trade;
+        Some(report)
+    }
+
+    // Check that the player can discard the cards now. Any player who owes cards can discard,
+    // not just the one whose turn it is.
+    pub fn check_discard(&self, player: PlayerId, cards: &Hand) -> Result<(), GameError> {
+        if let Phase::GameOver(_) = self.phase {
+            return Err(GameError::GameOver);
+        }
+        self.check_phase(Phase::Discard)?;
+        let count = self.get_discard(player);
+        if count == 0 {
+            return Err(GameError::NothingToDiscard);
+        }
+        if cards.total() != count {
+            return Err(GameError::WrongDiscardCount(count));
+        }
+        self.check_cost(player, cards)
+    }
+
+    // Discard the cards to the bank. The robber moves once everybody has discarded.
+    pub fn discard(&mut self, player: PlayerId, cards: &Hand) -> Result<(), GameError> {
+        self.check_discard(player, cards)?;
+        info!("{} discarded {}", player, cards);
+        self.pay(player, cards);
+        self.discards[player.0] = 0;
+        if self.discards.iter().all(|&count| count == 0) {
+            self.phase = Phase::MoveRobber;
+        }
+        Ok(())
+    }
+
+    // The players the current player can steal from with the robber on the hex: everyone else
+    // with a building on it and a card in hand.
+    pub fn get_robber_victims(&self, row: usize, column: usize) -> Vec<PlayerId> {
+        let mut victims: Vec<PlayerId> = self.board.get_hex_buildings(row, column)
+            .into_iter()
+            .map(|(_, building)| building.owner)
+            .filter(|&owner| owner != self.current && !self.players[owner.0].hand.is_empty())
+            .collect();
+        victims.sort();
+        victims.dedup();
+        victims
+    }
+
+    // Check that the player can move the robber to the hex and steal from the victim now.
+    // There has to be a victim if anyone on the hex can be stolen from.
+    pub fn check_robber(&self, player: PlayerId, row: usize, column: usize,
+                        victim: Option<PlayerId>) -> Result<(), GameError> {
+        self.check_turn(player)?;
+        self.check_phase(Phase::MoveRobber)?;
+        self.board.check_hex(row as isize, column as isize)?;
+        if !self.board.is_land(row, column) {
+            return Err(GameError::NotOnLand);
+        }
+        if self.board.get_robber() == Some((row, column)) {
+            return Err(GameError::RobberMustMove);
+        }
+        let victims = self.get_robber_victims(row, column);
+        match victim {
+            Some(victim) if !victims.contains(&victim) => Err(GameError::InvalidVictim(victim)),
+            None if !victims.is_empty() => Err(GameError::VictimRequired),
+            _ => Ok(()),
+        }
+    }
+
+    // Move the robber to another land hex and steal a random card from the victim, after a 7
+    // or a knight. Returns the stolen card.
+    pub fn move_robber(&mut self, player: PlayerId, row: usize, column: usize,
+                       victim: Option<PlayerId>) -> Result<Option<Resource>, GameError> {
+        self.check_robber(player, row, column, victim)?;
+        self.board.set_robber(row, column);
+        self.phase = self.resume;
+        Ok(victim.map(|victim| self.steal(player, victim)))
+    }
+
+    // Take a random card from the victim's hand, which can't be empty.
+    fn steal(&mut self, player: PlayerId, victim: PlayerId) -> Resource {
+        let hand = self.players[victim.0].hand;
+        let mut index = self.random.below(hand.total() as usize) as u32;
+        let mut stolen = RESOURCES[0];
+        for &resource in &RESOURCES {
+            if index < hand.get(resource) {
+                stolen = resource;
+                break;
+            }
+            index -= hand.get(resource);
+        }
+        info!("{} stole from {}", player, victim);
+        self.players[victim.0].hand.remove(stolen, 1);
+        self.players[player.0].hand.add(stolen, 1);
+        stolen
+    }
+
+    // Check that the player can offer the trade to the others now. Somebody has to have the
+    // cards it asks for.
+    pub fn check_offer_trade(&self, player: PlayerId, offer: &Offer) -> Result<(), GameError> {
+        self.check_turn(player)?;
+        self.check_phase(Phase::Trade)?;
+        if !offer.is_valid() {
+            return Err(GameError::InvalidTrade);
+        }
+        self.check_cost(player, &offer.give)?;
+        let takers = self.players.iter()
+            .enumerate()
+            .any(|(index, other)| index != player.0 && other.hand.contains(&offer.get));
+        if !takers {
+            return Err(GameError::NobodyCanAccept);
+        }
+        Ok(())
+    }
+
+    // Offer a trade to the others. It replaces the last offer and the responses to it.
+    pub fn offer_trade(&mut self, player: PlayerId, offer: &Offer) -> Result<(), GameError> {
+        self.check_offer_trade(player, offer)?;
+        info!("{} offers {} for {}", player, offer.give, offer.get);
+        self.negotiation = Some(Negotiation::new(*offer, self.players.len()));
+        Ok(())
+    }
+
+    // Check that the player can respond to the offer now. Anyone but the player whose turn it
+    // is can respond, but only with cards they have, and can only counter with cards the player
+    // whose turn it is has.
+    pub fn check_respond_trade(&self, player: PlayerId, response: &Response)
+        -> Result<(), GameError> {
+        if let Phase::GameOver(_) = self.phase {
+            return Err(GameError::GameOver);
+        }
+        self.check_phase(Phase::Trade)?;
+        let negotiation = match self.negotiation {
+            Some(ref negotiation) => negotiation,
+            None => return Err(GameError::NoTradeOffer),
+        };
+        if player.0 >= self.players.len() {
+            return Err(GameError::NoSuchPlayer(player));
+        }
+        if player == self.current {
+            return Err(GameError::OwnTrade);
+        }
+        match *response {
+            Response::Accepted => self.check_cost(player, &negotiation.offer.get),
+            Response::Rejected => Ok(()),
+            Response::Countered(ref offer) => {
+                if !offer.is_valid() {
+                    return Err(GameError::InvalidTrade);
+                }
+                self.check_cost(player, &offer.get)?;
+                self.check_cost(self.current, &offer.give)
+            }
+        }
+    }
+
+    // Accept, reject or counter the offer. A player can change their mind until the trade is
+    // confirmed.
+    pub fn respond_trade(&mut self, player: PlayerId, response: &Response)
+        -> Result<(), GameError> {
+        self.check_respond_trade(player, response)?;
+        debug!("{} responded to the trade", player);
+        self.negotiation.as_mut().unwrap().responses[player.0] = Some(*response);
+        Ok(())
+    }
+
+    // The offer the player whose turn it is and the partner would trade on.
+    fn check_trade_partner(&self, player: PlayerId, partner: PlayerId)
+        -> Result<Offer, GameError> {
+        self.check_turn(player)?;
+        self.check_phase(Phase::Trade)?;
+        let negotiation = match self.negotiation {
+            Some(ref negotiation) => negotiation,
+            None => return Err(GameError::NoTradeOffer),
+        };
+        if partner.0 >= self.players.len() {
+            return Err(GameError::NoSuchPlayer(partner));
+        }
+        let offer = match negotiation.get_agreed(partner.0) {
+            Some(offer) => offer,
+            None => return Err(GameError::NotAccepted(partner)),
+        };
+        // The hands can have changed since the offer, so check both sides again.
+        self.check_cost(player, &offer.give)?;
+        self.check_cost(partner, &offer.get)?;
+        Ok(offer)
+    }
+
+    pub fn check_confirm_trade(&self, player: PlayerId, partner: PlayerId)
+        -> Result<(), GameError> {
+        self.check_trade_partner(player, partner).map(|_| ())
+    }
+
+    // Make the trade the partner accepted or countered with. Both hands change at once.
+    pub fn confirm_trade(&mut self, player: PlayerId, partner: PlayerId)
+        -> Result<(), GameError> {
+        let offer = self.check_trade_partner(player, partner)?;
+        self.players[player.0].hand.remove_hand(&offer.give);
+        self.players[partner.0].hand.remove_hand(&offer.get);
+        self.players[player.0].hand.add_hand(&offer.get);
+        self.players[partner.0].hand.add_hand(&offer.give);
+        info!("{} traded {} to {} for {}", player, offer.give, partner, offer.get);
+        self.negotiation = None;
+        Ok(())
+    }
+
+    pub fn check_cancel_trade(&self, player: PlayerId) -> Result<(), GameError> {
+        self.check_turn(player)?;
+        self.check_phase(Phase::Trade)?;
+        if self.negotiation.is_none() {
+            return Err(GameError::NoTradeOffer);
+        }
+        Ok(())
+    }
+
+    // Take the offer off the table.
+    pub fn cancel_trade(&mut self, player: PlayerId) -> Result<(), GameError> {
+        self.check_cancel_trade(player)?;
+        self.negotiation = None;
+        Ok(())
+    }
+
+    // The number of each resource the player has to give the bank for one card.
+    pub fn get_bank_trade_rates(&self, player: PlayerId) -> [u32; 5] {
+        self.board.get_trade_rates(player)
+    }
+
+    // The most cards the player's hand can get from the bank, out of what the bank has left.
+    pub fn get_bank_trade_lots(&self, player: PlayerId) -> u32 {
+        get_lots(&self.players[player.0].hand, &self.get_bank_trade_rates(player),
+                 self.bank.get_cards())
+    }
+
+    // Every trade of a single lot the player could make with the bank now.
+    pub fn get_bank_trades(&self, player: PlayerId) -> Vec<Offer> {
+        let rates = self.get_bank_trade_rates(player);
+        let hand = &self.players[player.0].hand;
+        let mut trades = vec![];
+        for &give in &RESOURCES {
+            if hand.get(give) < rates[give.index()] {
+                continue;
+            }
+            for &get in &RESOURCES {
+                if get != give && self.bank.get(get) > 0 {
+                    trades.push(Offer::new(Hand::single(give, rates[give.index()]),
+                                           Hand::single(get, 1)));
+                }
+            }
+        }
+        trades
+    }
+
+    // Check that the player can make the trade with the bank now.
+    pub fn check_bank_trade(&self, player: PlayerId, offer: &Offer) -> Result<(), GameError> {
+        self.check_turn(player)?;
+        self.check_phase(Phase::Trade)?;
+        if !offer.is_valid() {
+            return Err(GameError::InvalidTrade);
+        }
+        let lots = match count_lots(&offer.give, &self.get_bank_trade_rates(player)) {
+            Ok(lots) => lots,
+            Err((resource, rate)) => return Err(GameError::TradeRate(resource, rate)),
+        };
+        if lots != offer.get.total() {
+            return Err(GameError::WrongLots(lots));
+        }
+        self.check_cost(player, &offer.give)?;
+        let shortfall = self.bank.get_cards().get_shortfall(&offer.get);
+        if !shortfall.is_empty() {
+            return Err(GameError::BankShort(shortfall));
+        }
+        Ok(())
+    }
+
+    // Trade with the bank at the player's rates.
+    pub fn bank_trade(&mut self, player: PlayerId, offer: &Offer) -> Result<(), GameError> {
+        self.check_bank_trade(player, offer)?;
+        self.pay(player, &offer.give);
+        self.bank.pay(&offer.get);
+        self.players[player.0].hand.add_hand(&offer.get);
+        info!("{} traded {} to the bank for {}", player, offer.give, offer.get);
+        Ok(())
+    }
+
+    pub fn check_end_trade(&self, player: PlayerId) -> Result<(), GameError> {
+        self.check_turn(player)?;
+        self.check_phase(Phase::Trade)
+    }
+
+    // Stop trading and start building. Any offer is withdrawn.
+    pub fn end_trade(&mut self, player: PlayerId) -> Result<(), GameError> {
+        self.check_end_trade(player)?;
+        self.negotiation = None;
+        self.phase = Phase::Build;
+        Ok(())
+    }
+
+    pub fn check_end_turn(&self, player: PlayerId) -> Result<(), GameError> {
+        self.check_turn(player)?;
+        match self.phase {
+            Phase::Trade | Phase::Build => Ok(()),
+            phase => Err(GameError::WrongPhase(phase)),
+        }
+    }
+
+    // Pass the dice to the next player.
+    pub fn end_turn(&mut self, player: PlayerId) -> Result<(), GameError> {
+        self.check_end_turn(player)?;
+        let current = &mut self.players[player.0];
+        let bought = current.new_dev_cards.drain(..).collect::<Vec<DevCard>>();
+        current.dev_cards.extend(bought);
+        self.played_dev_card = false;
+        self.negotiation = None;
+        self.turn += 1;
+        self.current = PlayerId((self.current.0 + 1) % self.players.len());
+        self.phase = Phase::Roll;
+        self.last_roll = None;
+        self.check_victory();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bank::BANK_CARDS;
+    use harbor::Harbor;
+    use random::Scripted;
+    use rules::VICTORY_TARGET;
+    use mapgen::generate_base_map;
+    use terrain::Resource;
+
+    // Pick land intersections far enough apart to all be settled, with a road from each.
+    fn pick_spots(board: &Board, count: usize) -> Vec<(usize, usize)> {
+        let mut spots: Vec<(usize, usize)> = vec![];
+        for intersection in board.get_intersections() {
+            if spots.len() == count {
+                break;
+            }
+            if board.get_intersection_kind(intersection) != IntersectionKind::Land {
+                continue;
+            }
+            let near = |other: usize| {
+                other == intersection ||
+                    board.get_intersection_neighbors(other).iter().any(|&n| {
+                        n == intersection || board.get_intersection_neighbors(n)
+                            .contains(&intersection)
+                    })
+            };
+            if spots.iter().any(|&(other, _)| near(other)) {
+                continue;
+            }
+            let road = board.get_intersection_edges(intersection)
+                .into_iter()
+                .find(|&edge| board.get_edge_kind(edge) != EdgeKind::Sea);
+            if let Some(road) = road {
+                spots.push((intersection, road));
+            }
+        }
+        assert_eq!(count, spots.len());
+        spots
+    }
+
+    // Give the player cards from the bank.
+    fn give(game: &mut GameState, player: PlayerId, hand: &Hand) {
+        game.bank.pay(hand);
+        game.players[player.0].hand.add_hand(hand);
+    }
+
+    fn count_cards(game: &GameState) -> Hand {
+        let mut cards = *game.get_bank().get_cards();
+        for player in &game.players {
+            cards.add_hand(&player.hand);
+        }
+        cards
+    }
+
+    fn make_game(players: usize) -> GameState {
+        GameState::new(generate_base_map(0, &Default::default()).unwrap(), players, 0)
+    }
+
+    // Play the setup and return the spots in the order they were placed.
+    fn set_up(game: &mut GameState) -> Vec<(usize, usize)> {
+        let players = game.get_players_size();
+        let spots = pick_spots(game.get_board(), 2 * players);
+        for &(intersection, road) in &spots {
+            let player = game.get_current_player();
+            game.place_settlement(player, intersection).unwrap();
+            game.place_road(player, road).unwrap();
+        }
+        spots
+    }
+
+    #[test]
+    fn test_robber_starts_on_desert() {
+        let game = make_game(3);
+        let (row, column) = game.get_board().get_robber().unwrap();
+        assert_eq!(TerrainKind::Desert, game.get_board().get_tile(row, column));
+    }
+
+    #[test]
+    fn test_setup_goes_in_snake_order() {
+        let mut game = make_game(3);
+        let spots = pick_spots(game.get_board(), 6);
+        let mut order = vec![];
+        for &(intersection, road) in &spots {
+            let player = game.get_current_player();
+            order.push(player.0);
+            assert_eq!(Phase::SetupSettlement, game.get_phase());
+            game.place_settlement(player, intersection).unwrap();
+            assert_eq!(Phase::SetupRoad(intersection), game.get_phase());
+            game.place_road(player, road).unwrap();
+        }
+        assert_eq!(vec![0, 1, 2, 2, 1, 0], order);
+        assert_eq!(Phase::Roll, game.get_phase());
+        assert_eq!(PlayerId(0), game.get_current_player());
+        for player in 0..3 {
+            let player = game.get_player(PlayerId(player));
+            assert_eq!(SETTLEMENTS - 2, player.settlements);
+            assert_eq!(ROADS - 2, player.roads);
+        }
+        // Only the second settlement collects resources.
+        let second = spots[3].0;
+        let productive = game.get_board().get_intersection_hexes(second)
+            .into_iter()
+            .filter(|&(row, column)| game.get_board().get_tile(row, column).is_productive())
+            .count();
+        assert_eq!(productive as u32, game.get_player(PlayerId(2)).hand.total());
+    }
+
+    #[test]
+    fn test_setup_rejects_bad_moves() {
+        let mut game = make_game(2);
+        let spots = pick_spots(game.get_board(), 2);
+        let (intersection, road) = spots[0];
+        assert_eq!(Err(GameError::NotYourTurn(PlayerId(0))),
+                   game.place_settlement(PlayerId(1), intersection));
+        assert_eq!(Err(GameError::WrongPhase(Phase::SetupSettlement)),
+                   game.place_road(PlayerId(0), road));
+        assert_eq!(Err(GameError::WrongPhase(Phase::SetupSettlement)), game.roll(PlayerId(0)));
+        game.place_settlement(PlayerId(0), intersection).unwrap();
+        // The road has to touch the settlement just placed.
+        assert_eq!(Err(GameError::NotConnected), game.place_road(PlayerId(0), spots[1].1));
+        game.place_road(PlayerId(0), road).unwrap();
+        // Nor can the next settlement go next to it.
+        let neighbors = game.get_board().get_intersection_neighbors(intersection);
+        for neighbor in neighbors {
+            let expected = match game.get_board().get_intersection_kind(neighbor) {
+                IntersectionKind::Land => GameError::DistanceRule(intersection),
+                _ => GameError::NotOnLand,
+            };
+            assert_eq!(Err(expected), game.place_settlement(PlayerId(1), neighbor));
+        }
+        assert_eq!(Err(GameError::Occupied), game.place_settlement(PlayerId(1), intersection));
+        assert_eq!(Err(GameError::Board(::board::BoardError::IntersectionOffBoard(0))),
+                   game.place_settlement(PlayerId(1), 0));
+    }
+
+    #[test]
+    fn test_turn_flow() {
+        let mut game = make_game(2);
+        set_up(&mut game);
+        let turn = game.get_turn();
+        assert_eq!(Err(GameError::WrongPhase(Phase::Roll)), game.end_turn(PlayerId(0)));
+        assert_eq!(Err(GameError::WrongPhase(Phase::Roll)), game.end_trade(PlayerId(0)));
+
+        game.resolve_roll(8);
+        assert_eq!(Phase::Trade, game.get_phase());
+        assert_eq!(Some(8), game.get_last_roll());
+        assert_eq!(Err(GameError::WrongPhase(Phase::Trade)), game.build_city(PlayerId(0), 0));
+        game.end_trade(PlayerId(0)).unwrap();
+        assert_eq!(Phase::Build, game.get_phase());
+        assert_eq!(Err(GameError::WrongPhase(Phase::Build)), game.roll(PlayerId(0)));
+        game.end_turn(PlayerId(0)).unwrap();
+        assert_eq!(PlayerId(1), game.get_current_player());
+        assert_eq!(Phase::Roll, game.get_phase());
+        assert_eq!(turn + 1, game.get_turn());
+
+        let (roll, _) = game.roll(PlayerId(1)).unwrap();
+        assert!((2..=12).contains(&roll));
+        // Ending the turn straight from trading skips building.
+        if game.get_phase() == Phase::MoveRobber {
+            let (row, column) = game.get_board().get_land_hexes()
+                .into_iter()
+                .find(|&hex| Some(hex) != game.get_board().get_robber())
+                .unwrap();
+            let victim = game.get_robber_victims(row, column).first().cloned();
+            game.move_robber(PlayerId(1), row, column, victim).unwrap();
+        }
+        game.end_turn(PlayerId(1)).unwrap();
+        assert_eq!(PlayerId(0), game.get_current_player());
+    }
+
+    #[test]
+    fn test_seven_moves_the_robber() {
+        let mut game = make_game(2);
+        set_up(&mut game);
+        let robber = game.get_board().get_robber().unwrap();
+        game.resolve_roll(7);
+        assert_eq!(Phase::MoveRobber, game.get_phase());
+        assert_eq!(Err(GameError::WrongPhase(Phase::MoveRobber)), game.end_trade(PlayerId(0)));
+        assert_eq!(Err(GameError::RobberMustMove),
+                   game.move_robber(PlayerId(0), robber.0, robber.1, None));
+        assert_eq!(Err(GameError::NotOnLand), game.move_robber(PlayerId(0), 0, 3, None));
+        let (row, column) = game.get_board().get_land_hexes()
+            .into_iter()
+            .find(|&(row, column)| {
+                (row, column) != robber && game.get_robber_victims(row, column).is_empty()
+            })
+            .unwrap();
+        assert_eq!(Err(GameError::InvalidVictim(PlayerId(1))),
+                   game.move_robber(PlayerId(0), row, column, Some(PlayerId(1))));
+        assert_eq!(Ok(None), game.move_robber(PlayerId(0), row, column, None));
+        assert_eq!(Some((row, column)), game.get_board().get_robber());
+        assert_eq!(Phase::Trade, game.get_phase());
+    }
+
+    #[test]
+    fn test_discard() {
+        let mut game = make_game(3);
+        set_up(&mut game);
+        for player in 0..3 {
+            let hand = game.players[player].hand;
+            game.pay(PlayerId(player), &hand);
+        }
+        give(&mut game, PlayerId(1), &Hand([3, 3, 3, 0, 0]));
+        give(&mut game, PlayerId(2), &Hand([2, 2, 2, 2, 0]));
+        game.resolve_roll(7);
+        assert_eq!(Phase::Discard, game.get_phase());
+        assert_eq!(0, game.get_discard(PlayerId(0)));
+        assert_eq!(4, game.get_discard(PlayerId(1)));
+        assert_eq!(4, game.get_discard(PlayerId(2)));
+        assert_eq!(Err(GameError::WrongPhase(Phase::Discard)),
+                   game.move_robber(PlayerId(0), 0, 0, None));
+        assert_eq!(Err(GameError::NothingToDiscard),
+                   game.discard(PlayerId(0), &Hand::default()));
+        assert_eq!(Err(GameError::WrongDiscardCount(4)),
+                   game.discard(PlayerId(1), &Hand([3, 0, 0, 0, 0])));
+        assert_eq!(Err(GameError::InsufficientResources(Hand([0, 0, 0, 0, 1]))),
+                   game.discard(PlayerId(1), &Hand([3, 0, 0, 0, 1])));
+
+        // Players discard in any order, not just in turn order.
+        let cards = count_cards(&game);
+        game.discard(PlayerId(2), &Hand([2, 2, 0, 0, 0])).unwrap();
+        assert_eq!(Phase::Discard, game.get_phase());
+        assert_eq!(Err(GameError::NothingToDiscard),
+                   game.discard(PlayerId(2), &Hand([0, 0, 2, 2, 0])));
+        game.discard(PlayerId(1), &Hand([1, 1, 2, 0, 0])).unwrap();
+        assert_eq!(Phase::MoveRobber, game.get_phase());
+        assert_eq!(Hand([2, 2, 1, 0, 0]), game.get_player(PlayerId(1)).hand);
+        assert_eq!(cards, count_cards(&game));
+    }
+
+    #[test]
+    fn test_robber_steals() {
+        let mut game = make_game(2);
+        let spots = set_up(&mut game);
+        // A hex next to player 1's first settlement that isn't next to player 0.
+        let (row, column) = game.get_board().get_intersection_hexes(spots[1].0)
+            .into_iter()
+            .find(|&(row, column)| {
+                game.get_board().is_land(row, column) &&
+                    Some((row, column)) != game.get_board().get_robber() &&
+                    game.get_board().get_hex_buildings(row, column)
+                        .iter()
+                        .all(|&(_, building)| building.owner == PlayerId(1))
+            })
+            .unwrap();
+        let hand = game.players[1].hand;
+        game.pay(PlayerId(1), &hand);
+        game.resolve_roll(7);
+        // Nobody on the hex has cards, so there is nobody to steal from.
+        assert!(game.get_robber_victims(row, column).is_empty());
+
+        give(&mut game, PlayerId(1), &Hand::single(Resource::Wool, 1));
+        assert_eq!(vec![PlayerId(1)], game.get_robber_victims(row, column));
+        assert_eq!(Err(GameError::VictimRequired),
+                   game.move_robber(PlayerId(0), row, column, None));
+        assert_eq!(Err(GameError::InvalidVictim(PlayerId(0))),
+                   game.move_robber(PlayerId(0), row, column, Some(PlayerId(0))));
+        let wool = game.get_player(PlayerId(0)).hand.get(Resource::Wool);
+        assert_eq!(Ok(Some(Resource::Wool)),
+                   game.move_robber(PlayerId(0), row, column, Some(PlayerId(1))));
+        assert!(game.get_player(PlayerId(1)).hand.is_empty());
+        assert_eq!(wool + 1, game.get_player(PlayerId(0)).hand.get(Resource::Wool));
+        assert_eq!(Phase::Trade, game.get_phase());
+    }
+
+    #[test]
+    fn test_production() {
+        let mut game = make_game(2);
+        let spots = set_up(&mut game);
+        let (row, column) = game.get_board().get_intersection_hexes(spots[0].0)
+            .into_iter()
+            .find(|&(row, column)| game.get_board().get_token(row, column).is_some())
+            .unwrap();
+        let token = game.get_board().get_token(row, column).unwrap();
+        let resource = game.get_board().get_tile(row, column).resource().unwrap();
+        let before = game.get_player(PlayerId(0)).hand.get(resource);
+        let report = game.resolve_roll(token.get_number()).unwrap();
+        assert!(before < game.get_player(PlayerId(0)).hand.get(resource));
+        assert!(0 < report.received[0].get(resource));
+        assert_eq!(Hand([BANK_CARDS; 5]), count_cards(&game));
+
+        // Nothing is produced under the robber.
+        game.board.set_robber(row, column);
+        let before = game.get_player(PlayerId(0)).hand;
+        game.phase = Phase::Roll;
+        game.resolve_roll(token.get_number());
+        assert_eq!(before.get(resource), game.get_player(PlayerId(0)).hand.get(resource));
+    }
+
+    #[test]
+    fn test_building() {
+        let mut game = make_game(2);
+        let spots = set_up(&mut game);
+        game.resolve_roll(2);
+        game.end_trade(PlayerId(0)).unwrap();
+        let (settlement, road) = spots[0];
+        let hand = game.players[0].hand;
+        game.pay(PlayerId(0), &hand);
+        assert_eq!(Err(GameError::InsufficientResources(CITY_COST)),
+                   game.build_city(PlayerId(0), settlement));
+
+        give(&mut game, PlayerId(0), &CITY_COST);
+        assert_eq!(Err(GameError::NotYourSettlement), game.build_city(PlayerId(0), spots[1].0));
+        game.build_city(PlayerId(0), settlement).unwrap();
+        assert_eq!(Some(Building::city(PlayerId(0))), game.get_board().get_building(settlement));
+        assert_eq!(CITIES - 1, game.get_player(PlayerId(0)).cities);
+        assert_eq!(SETTLEMENTS - 1, game.get_player(PlayerId(0)).settlements);
+        assert_eq!(3, game.get_victory_points(PlayerId(0)));
+
+        // A road continues from the end of the setup road.
+        let (a, b) = game.get_board().get_edge_endpoints(road);
+        let end = if a == settlement { b } else { a };
+        let next = game.get_board().get_intersection_edges(end)
+            .into_iter()
+            .find(|&edge| edge != road && game.check_pave(edge).is_ok())
+            .unwrap();
+        assert_eq!(Err(GameError::InsufficientResources(ROAD_COST)),
+                   game.place_road(PlayerId(0), next));
+        give(&mut game, PlayerId(0), &ROAD_COST);
+        game.players[0].roads = 0;
+        assert_eq!(Err(GameError::NoPiecesLeft(PieceKind::Road)),
+                   game.place_road(PlayerId(0), next));
+        game.players[0].roads = 1;
+        game.place_road(PlayerId(0), next).unwrap();
+        assert!(game.get_player(PlayerId(0)).hand.is_empty());
+
+        // A settlement can't go where no road of the player leads.
+        give(&mut game, PlayerId(0), &SETTLEMENT_COST);
+        let lonely = pick_spots(game.get_board(), 8)[7].0;
+        assert_eq!(Ok(()), game.check_site(lonely));
+        assert_eq!(Err(GameError::NotConnected), game.place_settlement(PlayerId(0), lonely));
+        assert_eq!(1, game.get_player(PlayerId(0)).hand.get(Resource::Wool));
+    }
+
+    #[test]
+    fn test_game_over() {
+        let mut game = make_game(2);
+        let spots = set_up(&mut game);
+        game.resolve_roll(2);
+        game.end_trade(PlayerId(0)).unwrap();
+        // Settlements for player 0 up to one point short.
+        let mut points = game.get_victory_points(PlayerId(0));
+        for intersection in game.get_board().get_intersections() {
+            if points + 1 >= VICTORY_TARGET {
+                break;
+            }
+            if game.check_site(intersection).is_ok() {
+                game.board.set_building(intersection, Some(Building::settlement(PlayerId(0))));
+                points += 1;
+            }
+        }
+        assert_eq!(VICTORY_TARGET - 1, game.get_victory_points(PlayerId(0)));
+        give(&mut game, PlayerId(0), &CITY_COST);
+        game.build_city(PlayerId(0), spots[0].0).unwrap();
+        assert_eq!(Phase::GameOver(PlayerId(0)), game.get_phase());
+        assert_eq!(Some(PlayerId(0)), game.get_winner());
+        assert_eq!(Err(GameError::GameOver), game.end_turn(PlayerId(0)));
+    }
+
+    // Set up a game and get player 0 to the build phase with the cards.
+    fn build_with(cards: Vec<DevCard>) -> GameState {
+        let mut game = make_game(2);
+        set_up(&mut game);
+        game.resolve_roll(2);
+        game.end_trade(PlayerId(0)).unwrap();
+        game.players[0].dev_cards = cards;
+        game
+    }
+
+    #[test]
+    fn test_buy_dev_card() {
+        let mut game = build_with(vec![]);
+        let hand = game.players[0].hand;
+        game.pay(PlayerId(0), &hand);
+        assert_eq!(Err(GameError::InsufficientResources(DEV_CARD_COST)),
+                   game.buy_dev_card(PlayerId(0)));
+        give(&mut game, PlayerId(0), &DEV_CARD_COST);
+        let card = game.buy_dev_card(PlayerId(0)).unwrap();
+        assert_eq!(24, game.get_deck().len());
+        assert!(game.get_player(PlayerId(0)).hand.is_empty());
+        if card != DevCard::VictoryPoint {
+            assert_eq!(Err(GameError::BoughtThisTurn(card)),
+                       game.check_dev_card(PlayerId(0), card));
+        }
+        game.end_turn(PlayerId(0)).unwrap();
+        assert_eq!(vec![card], game.get_player(PlayerId(0)).dev_cards);
+        assert!(game.get_player(PlayerId(0)).new_dev_cards.is_empty());
+    }
+
+    #[test]
+    fn test_one_dev_card_per_turn() {
+        let mut game = build_with(vec![DevCard::Knight, DevCard::Monopoly]);
+        assert_eq!(Err(GameError::NoDevCard(DevCard::YearOfPlenty)),
+                   game.play_year_of_plenty(PlayerId(0), Resource::Ore, Resource::Ore));
+        game.play_knight(PlayerId(0)).unwrap();
+        assert_eq!(Phase::MoveRobber, game.get_phase());
+        assert_eq!(Err(GameError::WrongPhase(Phase::MoveRobber)),
+                   game.play_monopoly(PlayerId(0), Resource::Ore));
+        let robber = game.get_board().get_robber().unwrap();
+        let (row, column) = game.get_board().get_land_hexes()
+            .into_iter()
+            .find(|&hex| hex != robber)
+            .unwrap();
+        let victim = game.get_robber_victims(row, column).first().cloned();
+        game.move_robber(PlayerId(0), row, column, victim).unwrap();
+        assert_eq!(Phase::Build, game.get_phase());
+        assert_eq!(1, game.get_player(PlayerId(0)).knights);
+        assert_eq!(Err(GameError::AlreadyPlayedDevCard),
+                   game.play_monopoly(PlayerId(0), Resource::Ore));
+        game.end_turn(PlayerId(0)).unwrap();
+        assert_eq!(Err(GameError::NotYourTurn(PlayerId(1))),
+                   game.play_monopoly(PlayerId(0), Resource::Ore));
+    }
+
+    #[test]
+    fn test_knight_before_rolling() {
+        let mut game = make_game(2);
+        set_up(&mut game);
+        game.players[0].dev_cards = vec![DevCard::Knight];
+        game.play_knight(PlayerId(0)).unwrap();
+        let robber = game.get_board().get_robber().unwrap();
+        let (row, column) = game.get_board().get_land_hexes()
+            .into_iter()
+            .find(|&hex| hex != robber)
+            .unwrap();
+        let victim = game.get_robber_victims(row, column).first().cloned();
+        game.move_robber(PlayerId(0), row, column, victim).unwrap();
+        assert_eq!(Phase::Roll, game.get_phase());
+    }
+
+    #[test]
+    fn test_road_building() {
+        let mut game = build_with(vec![DevCard::RoadBuilding]);
+        let hand = game.players[0].hand;
+        game.pay(PlayerId(0), &hand);
+        game.play_road_building(PlayerId(0)).unwrap();
+        for left in (1..3).rev() {
+            assert_eq!(Phase::FreeRoads(left), game.get_phase());
+            let edge = game.get_board().get_edges()
+                .into_iter()
+                .find(|&edge| game.check_road(PlayerId(0), edge).is_ok())
+                .unwrap();
+            game.place_road(PlayerId(0), edge).unwrap();
+        }
+        assert_eq!(Phase::Build, game.get_phase());
+        assert_eq!(ROADS - 4, game.get_player(PlayerId(0)).roads);
+        assert!(game.get_player(PlayerId(0)).hand.is_empty());
+
+        // With every edge taken, the card is kept.
+        let mut game = build_with(vec![DevCard::RoadBuilding]);
+        for edge in game.get_board().get_edges() {
+            if game.check_pave(edge).is_ok() {
+                game.board.set_road(edge, Some(Road::road(PlayerId(1))));
+            }
+        }
+        assert_eq!(Err(GameError::NoRoadSite), game.play_road_building(PlayerId(0)));
+        assert_eq!(vec![DevCard::RoadBuilding], game.get_player(PlayerId(0)).dev_cards);
+        assert_eq!(Phase::Build, game.get_phase());
+    }
+
+    #[test]
+    fn test_year_of_plenty_and_monopoly() {
+        let mut game = build_with(vec![DevCard::YearOfPlenty, DevCard::Monopoly]);
+        let ore = game.get_player(PlayerId(0)).hand.get(Resource::Ore);
+        let bank_ore = game.get_bank().get(Resource::Ore);
+        give(&mut game, PlayerId(1), &Hand::single(Resource::Ore, bank_ore - 1));
+        assert_eq!(Err(GameError::BankShort(Hand::single(Resource::Ore, 1))),
+                   game.play_year_of_plenty(PlayerId(0), Resource::Ore, Resource::Ore));
+        game.play_year_of_plenty(PlayerId(0), Resource::Ore, Resource::Wool).unwrap();
+        assert_eq!(ore + 1, game.get_player(PlayerId(0)).hand.get(Resource::Ore));
+        assert_eq!(0, game.get_bank().get(Resource::Ore));
+
+        game.played_dev_card = false;
+        let theirs = game.get_player(PlayerId(1)).hand.get(Resource::Ore);
+        assert_eq!(Ok(theirs), game.play_monopoly(PlayerId(0), Resource::Ore));
+        assert_eq!(0, game.get_player(PlayerId(1)).hand.get(Resource::Ore));
+        assert_eq!(ore + 1 + theirs, game.get_player(PlayerId(0)).hand.get(Resource::Ore));
+    }
+
+    #[test]
+    fn test_victory_point_card_wins() {
+        let mut game = build_with(vec![DevCard::VictoryPoint; 7]);
+        assert_eq!(VICTORY_TARGET - 1, game.get_victory_points(PlayerId(0)));
+        // Buy until the first victory point card, which wins straight away.
+        loop {
+            give(&mut game, PlayerId(0), &DEV_CARD_COST);
+            if game.buy_dev_card(PlayerId(0)).unwrap() == DevCard::VictoryPoint {
+                break;
+            }
+            assert_eq!(Phase::Build, game.get_phase());
+        }
+        assert_eq!(Some(PlayerId(0)), game.get_winner());
+    }
+
+    #[test]
+    fn test_victory_target() {
+        let board = generate_base_map(0, &Default::default()).unwrap();
+        let rules = Rules {
+            victory_target: 3,
+            ..Default::default()
+        };
+        let mut game = GameState::with_rules(board, 2, 0, rules);
+        let spots = set_up(&mut game);
+        assert_eq!(2, game.get_victory_points(PlayerId(0)));
+        game.resolve_roll(2);
+        game.end_trade(PlayerId(0)).unwrap();
+        give(&mut game, PlayerId(0), &CITY_COST);
+        game.build_city(PlayerId(0), spots[0].0).unwrap();
+        assert_eq!(Some(PlayerId(0)), game.get_winner());
+    }
+
+    #[test]
+    fn test_largest_army() {
+        let mut game = make_game(2);
+        set_up(&mut game);
+        game.players[0].dev_cards = vec![DevCard::Knight; 3];
+        game.players[1].dev_cards = vec![DevCard::VictoryPoint];
+        for knights in 1..4 {
+            game.play_knight(PlayerId(0)).unwrap();
+            let robber = game.get_board().get_robber().unwrap();
+            let (row, column) = game.get_board().get_land_hexes()
+                .into_iter()
+                .find(|&hex| hex != robber)
+                .unwrap();
+            let victim = game.get_robber_victims(row, column).first().cloned();
+            game.move_robber(PlayerId(0), row, column, victim).unwrap();
+            assert_eq!(knights == 3, game.get_largest_army() == Some(PlayerId(0)));
+            game.resolve_roll(2);
+            game.end_turn(PlayerId(0)).unwrap();
+            game.resolve_roll(2);
+            game.end_turn(PlayerId(1)).unwrap();
+        }
+        assert!(game.get_score(PlayerId(0)).largest_army);
+        assert_eq!(4, game.get_victory_points(PlayerId(0)));
+        // Victory point cards are hidden from the public score.
+        assert_eq!(3, game.get_victory_points(PlayerId(1)));
+        assert_eq!(2, game.get_public_victory_points(PlayerId(1)));
+    }
+
+    #[test]
+    fn test_trade() {
+        let mut game = make_game(3);
+        set_up(&mut game);
+        for player in 0..3 {
+            let hand = game.players[player].hand;
+            game.pay(PlayerId(player), &hand);
+        }
+        let brick = Hand::single(Resource::Brick, 1);
+        let ore = Hand::single(Resource::Ore, 1);
+        give(&mut game, PlayerId(0), &Hand::single(Resource::Brick, 2));
+        give(&mut game, PlayerId(1), &ore);
+        give(&mut game, PlayerId(2), &ore);
+        game.phase = Phase::Trade;
+
+        let offer = Offer::new(Hand::single(Resource::Brick, 2), ore);
+        assert_eq!(Err(GameError::NoTradeOffer),
+                   game.respond_trade(PlayerId(1), &Response::Accepted));
+        assert_eq!(Err(GameError::NotYourTurn(PlayerId(0))),
+                   game.offer_trade(PlayerId(1), &offer));
+        assert_eq!(Err(GameError::InvalidTrade), game.offer_trade(PlayerId(0), &Offer::default()));
+        let greedy = Offer::new(Hand::single(Resource::Brick, 3), ore);
+        assert_eq!(Err(GameError::InsufficientResources(brick)),
+                   game.offer_trade(PlayerId(0), &greedy));
+        // Nobody has two ore to give.
+        let unanswerable = Offer::new(brick, Hand::single(Resource::Ore, 2));
+        assert_eq!(Err(GameError::NobodyCanAccept), game.offer_trade(PlayerId(0), &unanswerable));
+        game.offer_trade(PlayerId(0), &offer).unwrap();
+        assert_eq!(Err(GameError::OwnTrade), game.respond_trade(PlayerId(0), &Response::Accepted));
+        game.respond_trade(PlayerId(1), &Response::Accepted).unwrap();
+        game.respond_trade(PlayerId(2), &Response::Rejected).unwrap();
+        assert_eq!(Err(GameError::NotAccepted(PlayerId(2))),
+                   game.confirm_trade(PlayerId(0), PlayerId(2)));
+        // A counteroffer can't ask for more than the player offering has.
+        let counter = Offer::new(Hand::single(Resource::Brick, 3), ore);
+        assert_eq!(Err(GameError::InsufficientResources(brick)),
+                   game.respond_trade(PlayerId(2), &Response::Countered(counter)));
+        let counter = Offer::new(brick, ore);
+        game.respond_trade(PlayerId(2), &Response::Countered(counter)).unwrap();
+        game.confirm_trade(PlayerId(0), PlayerId(2)).unwrap();
+        let mut hand = brick;
+        hand.add_hand(&ore);
+        assert_eq!(hand, game.get_player(PlayerId(0)).hand);
+        assert_eq!(brick, game.get_player(PlayerId(2)).hand);
+        assert_eq!(None, game.get_negotiation());
+
+        // The partner's hand is checked again when the trade is confirmed.
+        game.offer_trade(PlayerId(0), &Offer::new(brick, ore)).unwrap();
+        game.respond_trade(PlayerId(1), &Response::Accepted).unwrap();
+        game.pay(PlayerId(1), &ore);
+        assert_eq!(Err(GameError::InsufficientResources(ore)),
+                   game.confirm_trade(PlayerId(0), PlayerId(1)));
+        assert_eq!(hand, game.get_player(PlayerId(0)).hand);
+
+        game.end_trade(PlayerId(0)).unwrap();
+        assert_eq!(None, game.get_negotiation());
+        assert_eq!(Err(GameError::WrongPhase(Phase::Build)),
+                   game.offer_trade(PlayerId(0), &Offer::new(brick, ore)));
+    }
+
+    #[test]
+    fn test_bank_trade() {
+        let mut game = make_game(2);
+        set_up(&mut game);
+        let hand = game.players[0].hand;
+        game.pay(PlayerId(0), &hand);
+        // A 2:1 wool harbor by a new settlement of player 0.
+        let (edge, intersection) = game.get_board().get_edges()
+            .into_iter()
+            .filter(|&edge| game.get_board().get_edge_kind(edge) == EdgeKind::Coast)
+            .filter_map(|edge| {
+                let (a, b) = game.get_board().get_edge_endpoints(edge);
+                [a, b].iter()
+                    .cloned()
+                    .find(|&end| game.check_site(end).is_ok())
+                    .map(|end| (edge, end))
+            })
+            .next()
+            .unwrap();
+        game.board.set_harbor(edge, Some(Harbor::Special(Resource::Wool)));
+        game.board.set_building(intersection, Some(Building::settlement(PlayerId(0))));
+        assert_eq!([4, 4, 4, 4, 2], game.get_bank_trade_rates(PlayerId(0)));
+        give(&mut game, PlayerId(0), &Hand([9, 0, 0, 0, 3]));
+        assert_eq!(3, game.get_bank_trade_lots(PlayerId(0)));
+        assert_eq!(8, game.get_bank_trades(PlayerId(0)).len());
+        game.phase = Phase::Trade;
+
+        let ore = |count| Hand::single(Resource::Ore, count);
+        let brick = |count| Hand::single(Resource::Brick, count);
+        assert_eq!(Err(GameError::TradeRate(Resource::Brick, 4)),
+                   game.bank_trade(PlayerId(0), &Offer::new(brick(6), ore(1))));
+        assert_eq!(Err(GameError::WrongLots(2)),
+                   game.bank_trade(PlayerId(0), &Offer::new(brick(8), ore(1))));
+        assert_eq!(Err(GameError::InvalidTrade),
+                   game.bank_trade(PlayerId(0), &Offer::new(brick(4), brick(1))));
+        let cards = count_cards(&game);
+        game.bank_trade(PlayerId(0), &Offer::new(brick(8), ore(2))).unwrap();
+        assert_eq!(Hand([1, 0, 2, 0, 3]), game.get_player(PlayerId(0)).hand);
+        assert_eq!(cards, count_cards(&game));
+
+        // The bank can only give what it has.
+        let left = game.get_bank().get(Resource::Ore);
+        give(&mut game, PlayerId(1), &ore(left));
+        assert_eq!(1, game.get_bank_trade_lots(PlayerId(0)));
+        for &resource in &[Resource::Brick, Resource::Lumber, Resource::Grain] {
+            let left = game.get_bank().get(resource);
+            give(&mut game, PlayerId(1), &Hand::single(resource, left));
+        }
+        // Only wool is left, which the wool lot can't get.
+        assert_eq!(0, game.get_bank_trade_lots(PlayerId(0)));
+        assert!(game.get_bank_trades(PlayerId(0)).is_empty());
+        game.pay(PlayerId(1), &Hand::single(Resource::Grain, 1));
+        assert_eq!(1, game.get_bank_trade_lots(PlayerId(0)));
+        assert_eq!(Err(GameError::BankShort(ore(1))),
+                   game.bank_trade(PlayerId(0), &Offer::new(Hand::single(Resource::Wool, 2),
+                                                            ore(1))));
+        game.bank_trade(PlayerId(0), &Offer::new(Hand::single(Resource::Wool, 2),
+                                                  Hand::single(Resource::Grain, 1))).unwrap();
+    }
+
+    #[test]
+    fn test_scripted_dice() {
+        let board = generate_base_map(0, &Default::default()).unwrap();
+        let random = Scripted::new(0, &[7, 8], &[]).unwrap();
+        let mut game = GameState::with_randomness(board, 2, Default::default(), Box::new(random));
+        assert_eq!(make_game(2).get_deck(), game.get_deck());
+        set_up(&mut game);
+        assert_eq!(7, game.roll(PlayerId(0)).unwrap().0);
+        let robber = game.get_board().get_robber().unwrap();
+        let (row, column) = game.get_board().get_land_hexes()
+            .into_iter()
+            .find(|&hex| hex != robber)
+            .unwrap();
+        let victim = game.get_robber_victims(row, column).first().cloned();
+        game.move_robber(PlayerId(0), row, column, victim).unwrap();
+        game.end_turn(PlayerId(0)).unwrap();
+        assert_eq!(8, game.roll(PlayerId(1)).unwrap().0);
+    }
+}
@@ -0,0 +1,107 @@
+// Counts of resource cards, for the hands of the players and the prices of things.
+
+use std::fmt;
+
+use terrain::{Resource, RESOURCES};
+
+// The number of cards of each resource, indexed by Resource::index.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Hand(pub [u32; 5]);
+
+pub const ROAD_COST: Hand = Hand([1, 1, 0, 0, 0]);
+pub const SETTLEMENT_COST: Hand = Hand([1, 1, 0, 1, 1]);
+pub const CITY_COST: Hand = Hand([0, 0, 3, 2, 0]);
+
+impl Hand {
+    // A hand of count cards of one resource.
+    pub fn single(resource: Resource, count: u32) -> Hand {
+        let mut hand: Hand = Default::default();
+        hand.add(resource, count);
+        hand
+    }
+
+    pub fn get(&self, resource: Resource) -> u32 {
+        self.0[resource.index()]
+    }
+
+    pub fn add(&mut self, resource: Resource, count: u32) {
+        self.0[resource.index()] += count;
+    }
+
+    pub fn remove(&mut self, resource: Resource, count: u32) {
+        assert!(count <= self.get(resource));
+        self.0[resource.index()] -= count;
+    }
+
+    pub fn total(&self) -> u32 {
+        self.0.iter().sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total() == 0
+    }
+
+    // Whether the hand has at least as many of every resource as the other.
+    pub fn contains(&self, other: &Hand) -> bool {
+        RESOURCES.iter().all(|&resource| other.get(resource) <= self.get(resource))
+    }
+
+    // The cards the hand is short of to pay the cost.
+    pub fn get_shortfall(&self, cost: &Hand) -> Hand {
+        let mut shortfall: Hand = Default::default();
+        for &resource in &RESOURCES {
+            shortfall.add(resource, cost.get(resource).saturating_sub(self.get(resource)));
+        }
+        shortfall
+    }
+
+    pub fn add_hand(&mut self, other: &Hand) {
+        for &resource in &RESOURCES {
+            self.add(resource, other.get(resource));
+        }
+    }
+
+    pub fn remove_hand(&mut self, other: &Hand) {
+        assert!(self.contains(other));
+        for &resource in &RESOURCES {
+            self.remove(resource, other.get(resource));
+        }
+    }
+}
+
+impl fmt::Display for Hand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cards: Vec<String> = RESOURCES.iter()
+            .filter(|&&resource| self.get(resource) > 0)
+            .map(|&resource| format!("{} {}", self.get(resource), resource))
+            .collect();
+        if cards.is_empty() {
+            write!(f, "nothing")
+        } else {
+            write!(f, "{}", cards.join(", "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hand() {
+        let mut hand = Hand::single(Resource::Brick, 2);
+        hand.add(Resource::Lumber, 1);
+        assert_eq!(3, hand.total());
+        assert!(hand.contains(&ROAD_COST));
+        assert!(!hand.contains(&SETTLEMENT_COST));
+        let mut shortfall = Hand::single(Resource::Grain, 1);
+        shortfall.add(Resource::Wool, 1);
+        assert_eq!(shortfall, hand.get_shortfall(&SETTLEMENT_COST));
+        assert!(hand.get_shortfall(&ROAD_COST).is_empty());
+        hand.remove_hand(&ROAD_COST);
+        assert_eq!(Hand::single(Resource::Brick, 1), hand);
+        hand.add_hand(&CITY_COST);
+        assert_eq!("1 brick, 3 ore, 2 grain", hand.to_string());
+        assert_eq!("nothing", Hand::default().to_string());
+    }
+}
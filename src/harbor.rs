@@ -0,0 +1,52 @@
+// Harbors let players trade with the bank at better rates.
+//
+// A harbor sits on a coastal edge. A player with a settlement or city on either end of the edge
+// can use it.
+
+use std::fmt;
+
+use terrain::Resource;
+
+// Anyone can trade 4 of a resource for 1 of any other.
+pub const BANK_RATE: u32 = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Harbor {
+    // Trade 3 of any one resource for 1 of any other.
+    Generic,
+    // Trade 2 of the resource for 1 of any other.
+    Special(Resource),
+}
+
+impl Harbor {
+    // The number of the resource to give for one of another, if the harbor takes the resource.
+    pub fn get_rate(&self, resource: Resource) -> Option<u32> {
+        match *self {
+            Harbor::Generic => Some(3),
+            Harbor::Special(r) if r == resource => Some(2),
+            Harbor::Special(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Harbor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Harbor::Generic => write!(f, "3:1 harbor"),
+            Harbor::Special(resource) => write!(f, "2:1 {} harbor", resource),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_harbor_rates() {
+        assert_eq!(Some(3), Harbor::Generic.get_rate(Resource::Ore));
+        assert_eq!(Some(2), Harbor::Special(Resource::Ore).get_rate(Resource::Ore));
+        assert_eq!(None, Harbor::Special(Resource::Ore).get_rate(Resource::Wool));
+        assert_eq!("2:1 wool harbor", Harbor::Special(Resource::Wool).to_string());
+    }
+}
@@ -0,0 +1,147 @@
+// The actions taken in a game, for undo and redo.
+//
+// Every action applied through GameState::apply is recorded with what came of it, and with the
+// few things it overwrote that neither says, like the phase it was taken in or where the robber
+// was. Undoing it works out from those how to take it back exactly: a build gives back the piece
+// and the cards, a roll takes back what it produced, a steal gives the card back. Undoing a roll
+// or a steal also puts back the random number generator, so redoing it, which takes the action
+// again, comes out the same.
+
+use action::{Action, Outcome};
+use game::Phase;
+use hand::Hand;
+use piece::PlayerId;
+use random::RandomSource;
+use trade::Negotiation;
+
+// When undo is allowed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum UndoPolicy {
+    // Anything can be undone, for hotseat games and analysis.
+    #[default]
+    Casual,
+    // Nothing can be undone once it showed someone something they couldn't know before, like
+    // the dice, a drawn card or another player's hand.
+    Competitive,
+}
+
+impl UndoPolicy {
+    pub fn allows(&self, record: &Record) -> bool {
+        match *self {
+            UndoPolicy::Casual => true,
+            UndoPolicy::Competitive => !record.reveals_hidden(),
+        }
+    }
+}
+
+// An applied action and what came of it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Record {
+    pub player: PlayerId,
+    pub action: Action,
+    pub outcome: Outcome,
+}
+
+impl Record {
+    // Whether the action showed anyone hidden information.
+    pub fn reveals_hidden(&self) -> bool {
+        match self.action {
+            Action::Roll | Action::BuyDevCard | Action::PlayMonopoly(_) => true,
+            _ => matches!(self.outcome, Outcome::Stole(_)),
+        }
+    }
+}
+
+// What undoing an action needs besides its record: the parts of the state it overwrote, which
+// are the bookkeeping of the turn, and a few counts it leaves no trace of.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Revert {
+    pub phase: Phase,
+    pub resume: Phase,
+    pub last_roll: Option<u8>,
+    pub played_dev_card: bool,
+    pub longest_road: Option<PlayerId>,
+    pub largest_army: Option<PlayerId>,
+    pub negotiation: Option<Negotiation>,
+    pub robber: Option<(usize, usize)>,
+    // The generator before a roll or a steal.
+    pub random: Option<RandomSource>,
+    // The cards the second settlement of the setup collected.
+    pub collected: Hand,
+    // The cards monopoly took from each player.
+    pub taken: Vec<u32>,
+    // Where the card played was among the player's development cards, or how many cards bought
+    // during the turn ending it made playable.
+    pub dev_cards: usize,
+}
+
+// The records of the actions done, each with what undoing it needs, and of the actions undone.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct History {
+    done: Vec<(Record, Revert)>,
+    undone: Vec<Record>,
+}
+
+impl History {
+    // The actions applied so far, in order, leaving out the undone ones.
+    pub fn get_records(&self) -> Vec<&Record> {
+        self.done.iter().map(|(record, _)| record).collect()
+    }
+
+    // The record that undo would take back.
+    pub fn get_last(&self) -> Option<&Record> {
+        self.done.last().map(|(record, _)| record)
+    }
+
+    // The record that redo would take again.
+    pub fn get_next(&self) -> Option<&Record> {
+        self.undone.last()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    // Record an action applied. Anything undone can't be redone anymore.
+    pub fn push(&mut self, record: Record, revert: Revert) {
+        self.done.push((record, revert));
+        self.undone.clear();
+    }
+
+    // Move the last action to the undone ones. Returns it, to be taken back.
+    pub fn undo(&mut self) -> Option<(Record, Revert)> {
+        self.done.pop().map(|(record, revert)| {
+            self.undone.push(record.clone());
+            (record, revert)
+        })
+    }
+
+    // Move the last undone action back to the done ones, once it was taken again.
+    pub fn redo(&mut self, revert: Revert) {
+        if let Some(record) = self.undone.pop() {
+            self.done.push((record, revert));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use terrain::Resource;
+
+    #[test]
+    fn test_reveals_hidden() {
+        let record = |action, outcome| Record {
+            player: PlayerId(0),
+            action,
+            outcome,
+        };
+        let robber = Action::MoveRobber { row: 1, column: 1, victim: Some(PlayerId(1)) };
+        assert!(record(Action::Roll, Outcome::Rolled(7, None)).reveals_hidden());
+        assert!(record(robber, Outcome::Stole(Resource::Ore)).reveals_hidden());
+        assert!(!record(Action::EndTurn, Outcome::Done).reveals_hidden());
+        let record = record(Action::PlayMonopoly(Resource::Ore), Outcome::Took(2));
+        assert!(!UndoPolicy::Competitive.allows(&record));
+        assert!(UndoPolicy::Casual.allows(&record));
+    }
+}
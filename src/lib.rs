@@ -0,0 +1,33 @@
+#[macro_use]
+extern crate log;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+
+pub mod action;
+pub mod bank;
+pub mod board;
+pub mod coord;
+pub mod devcard;
+pub mod error;
+pub mod fairness;
+pub mod game;
+pub mod hand;
+pub mod harbor;
+pub mod history;
+pub mod longest_road;
+pub mod mapgen;
+pub mod piece;
+pub mod production;
+pub mod random;
+pub mod replay;
+pub mod rules;
+pub mod save;
+pub mod scoring;
+pub mod shape;
+pub mod snapshot;
+pub mod terrain;
+pub mod topology;
+pub mod trade;
+pub mod triangle;
@@ -0,0 +1,153 @@
+// The longest road.
+//
+// A player's road length is the longest trail along their roads: no road is used twice, but
+// the trail can cross itself. Another player's settlement or city breaks the road there.
+// The card goes to the first player with a road of five or more, and then to whoever builds a
+// strictly longer one. If the holder's road is broken and several players tie for the longest,
+// the card is set aside until one of them has the longest road alone.
+
+use board::Board;
+use piece::PlayerId;
+
+pub const LONGEST_ROAD_MIN: u32 = 5;
+
+// The length of the player's longest road.
+pub fn get_road_length(board: &Board, player: PlayerId) -> u32 {
+    let mut used = vec![];
+    let mut longest = 0;
+    for edge in board.get_roads(player) {
+        let (a, b) = board.get_edge_endpoints(edge);
+        for &start in &[a, b] {
+            longest = longest.max(get_trail(board, player, start, &mut used));
+        }
+    }
+    longest
+}
+
+// The longest trail of the player's roads from the intersection that doesn't use the roads
+// already used.
+fn get_trail(board: &Board, player: PlayerId, intersection: usize, used: &mut Vec<usize>) -> u32 {
+    let mut longest = 0;
+    for edge in board.get_intersection_edges(intersection) {
+        if board.get_edge_owner(edge) != Some(player) || used.contains(&edge) {
+            continue;
+        }
+        let (a, b) = board.get_edge_endpoints(edge);
+        let next = if a == intersection { b } else { a };
+        let broken = match board.get_intersection_owner(next) {
+            Some(owner) => owner != player,
+            None => false,
+        };
+        used.push(edge);
+        let length = 1 + if broken { 0 } else { get_trail(board, player, next, used) };
+        used.pop();
+        longest = longest.max(length);
+    }
+    longest
+}
+
+// Who holds the longest road given the road lengths of the players, indexed by player, and who
+// held it before.
+pub fn award_longest_road(holder: Option<PlayerId>, lengths: &[u32]) -> Option<PlayerId> {
+    let longest = lengths.iter().cloned().max().unwrap_or(0);
+    if longest < LONGEST_ROAD_MIN {
+        return None;
+    }
+    if let Some(holder) = holder {
+        if lengths[holder.0] == longest {
+            return Some(holder);
+        }
+    }
+    let leaders: Vec<usize> = (0..lengths.len()).filter(|&p| lengths[p] == longest).collect();
+    if leaders.len() == 1 {
+        Some(PlayerId(leaders[0]))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use board::IntersectionKind;
+    use mapgen::generate_base_map;
+    use piece::{Building, Road};
+
+    // The edge between two neighboring intersections.
+    fn get_edge(board: &Board, a: usize, b: usize) -> usize {
+        board.get_intersection_edges(a)
+            .into_iter()
+            .find(|&edge| board.get_intersection_edges(b).contains(&edge))
+            .unwrap()
+    }
+
+    // Walk the land intersections from the start without going back, and return the
+    // intersections visited.
+    fn walk(board: &Board, start: usize, edges: usize) -> Vec<usize> {
+        let mut path = vec![start];
+        while path.len() <= edges {
+            let last = *path.last().unwrap();
+            let next = board.get_intersection_neighbors(last)
+                .into_iter()
+                .find(|&next| {
+                    board.get_intersection_kind(next) == IntersectionKind::Land &&
+                        !path.contains(&next)
+                })
+                .unwrap();
+            path.push(next);
+        }
+        path
+    }
+
+    fn pave(board: &mut Board, path: &[usize], player: PlayerId) {
+        for pair in path.windows(2) {
+            let edge = get_edge(board, pair[0], pair[1]);
+            board.set_road(edge, Some(Road::road(player)));
+        }
+    }
+
+    #[test]
+    fn test_road_length() {
+        let mut board = generate_base_map(0, &Default::default()).unwrap();
+        let path = walk(&board, board.get_hex_intersections(3, 3)[0], 6);
+        pave(&mut board, &path, PlayerId(0));
+        assert_eq!(6, get_road_length(&board, PlayerId(0)));
+        assert_eq!(0, get_road_length(&board, PlayerId(1)));
+
+        // The player's own buildings don't break the road, but others' do.
+        board.set_building(path[2], Some(Building::settlement(PlayerId(0))));
+        assert_eq!(6, get_road_length(&board, PlayerId(0)));
+        board.set_building(path[2], Some(Building::city(PlayerId(1))));
+        assert_eq!(4, get_road_length(&board, PlayerId(0)));
+    }
+
+    #[test]
+    fn test_road_around_a_hex() {
+        let mut board = generate_base_map(0, &Default::default()).unwrap();
+        let mut ring = board.get_hex_intersections(3, 3).to_vec();
+        ring.push(ring[0]);
+        pave(&mut board, &ring, PlayerId(0));
+        assert_eq!(6, get_road_length(&board, PlayerId(0)));
+        // A road going off the ring makes a trail that goes around and then leaves.
+        let spur = board.get_intersection_neighbors(ring[0])
+            .into_iter()
+            .find(|next| !ring.contains(next))
+            .unwrap();
+        pave(&mut board, &[ring[0], spur], PlayerId(0));
+        assert_eq!(7, get_road_length(&board, PlayerId(0)));
+    }
+
+    #[test]
+    fn test_award_longest_road() {
+        assert_eq!(None, award_longest_road(None, &[4, 3]));
+        assert_eq!(Some(PlayerId(0)), award_longest_road(None, &[5, 3]));
+        assert_eq!(None, award_longest_road(None, &[5, 5]));
+        // Ties don't take the card from the holder.
+        assert_eq!(Some(PlayerId(0)), award_longest_road(Some(PlayerId(0)), &[6, 6]));
+        assert_eq!(Some(PlayerId(1)), award_longest_road(Some(PlayerId(0)), &[6, 7]));
+        // A broken road loses the card to the longest, or sets it aside if they tie.
+        assert_eq!(Some(PlayerId(1)), award_longest_road(Some(PlayerId(0)), &[3, 6, 5]));
+        assert_eq!(None, award_longest_road(Some(PlayerId(0)), &[3, 6, 6]));
+        assert_eq!(None, award_longest_road(Some(PlayerId(0)), &[4, 3, 2]));
+    }
+}
@@ -0,0 +1,340 @@
+// Random maps.
+//
+// The terrain tiles are shuffled onto the land hexes of a shape, and then the number tokens are
+// shuffled onto the productive tiles. The same seed always gives the same map.
+// If the number tokens are asked to be spread out, or the map is asked to be fair, the map is
+// shuffled again until it keeps to the options. Maps that don't can also be repaired by swapping
+// tiles and tokens around, which finds a good map in far fewer shuffles.
+
+use std::error::Error;
+use std::fmt;
+
+use board::Board;
+use fairness::{analyze, FairnessThresholds};
+use random::{shuffle, Random, Randomness};
+use shape::{HexKind, Shape};
+use terrain::{NumberToken, TerrainKind};
+
+// The terrain tiles of the base game.
+pub const BASE_TILES: [TerrainKind; 19] = [
+    TerrainKind::Hills, TerrainKind::Hills, TerrainKind::Hills,
+    TerrainKind::Forest, TerrainKind::Forest, TerrainKind::Forest, TerrainKind::Forest,
+    TerrainKind::Mountains, TerrainKind::Mountains, TerrainKind::Mountains,
+    TerrainKind::Fields, TerrainKind::Fields, TerrainKind::Fields, TerrainKind::Fields,
+    TerrainKind::Pasture, TerrainKind::Pasture, TerrainKind::Pasture, TerrainKind::Pasture,
+    TerrainKind::Desert,
+];
+
+// The number tokens of the base game.
+pub const BASE_NUMBERS: [u8; 18] = [2, 3, 3, 4, 4, 5, 5, 6, 6, 8, 8, 9, 9, 10, 10, 11, 11, 12];
+
+// How many times the map is shuffled before giving up on the options.
+pub const MAX_ATTEMPTS: usize = 10000;
+
+// How many swaps are tried when repairing a shuffled map.
+pub const REPAIR_STEPS: usize = 200;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MapOptions {
+    // No 6 or 8 next to another 6 or 8.
+    pub separate_red_numbers: bool,
+    // No two neighboring hexes with the same number.
+    pub separate_equal_numbers: bool,
+    // Reject maps outside the thresholds.
+    pub fairness: Option<FairnessThresholds>,
+    // Swap tiles and tokens on a map that doesn't keep to the options before rejecting it.
+    pub repair: bool,
+}
+
+impl MapOptions {
+    // The options most groups play with.
+    pub fn balanced() -> MapOptions {
+        MapOptions {
+            separate_red_numbers: true,
+            separate_equal_numbers: true,
+            fairness: Some(Default::default()),
+            repair: true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MapError {
+    // The number of tiles doesn't match the number of land hexes.
+    TileCount { tiles: usize, hexes: usize },
+    // The tiles go on land hexes, so none of them can be sea.
+    SeaTile,
+    // The number of tokens doesn't match the number of productive tiles.
+    NumberCount { numbers: usize, tiles: usize },
+    InvalidNumber(u8),
+    // No layout of the tokens that kept to the options was found.
+    Unsatisfiable,
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MapError::TileCount { tiles, hexes } =>
+                write!(f, "{} tiles for {} land hexes", tiles, hexes),
+            MapError::SeaTile => write!(f, "sea can't be one of the land tiles"),
+            MapError::NumberCount { numbers, tiles } =>
+                write!(f, "{} number tokens for {} productive tiles", numbers, tiles),
+            MapError::InvalidNumber(number) => write!(f, "{} is not a number token", number),
+            MapError::Unsatisfiable =>
+                write!(f, "no layout of the number tokens kept to the map options"),
+        }
+    }
+}
+
+impl Error for MapError {}
+
+// Shuffle the tiles onto the land hexes of the shape and the numbers onto the productive tiles.
+pub fn generate_map(shape: &Shape, tiles: &[TerrainKind], numbers: &[u8], seed: u64,
+                    options: &MapOptions) -> Result<Board, MapError> {
+    generate_map_with(shape, tiles, numbers, &mut Random::new(seed), options)
+}
+
+// Generate a map with the shuffles and swaps coming from the randomness.
+pub fn generate_map_with(shape: &Shape, tiles: &[TerrainKind], numbers: &[u8],
+                         random: &mut dyn Randomness, options: &MapOptions)
+    -> Result<Board, MapError> {
+    let hexes = shape.count(HexKind::Land);
+    if tiles.len() != hexes {
+        return Err(MapError::TileCount { tiles: tiles.len(), hexes });
+    }
+    if tiles.contains(&TerrainKind::Sea) {
+        return Err(MapError::SeaTile);
+    }
+    let productive = tiles.iter().filter(|tile| tile.is_productive()).count();
+    if numbers.len() != productive {
+        return Err(MapError::NumberCount { numbers: numbers.len(), tiles: productive });
+    }
+    let mut tokens = Vec::with_capacity(numbers.len());
+    for &number in numbers {
+        tokens.push(NumberToken::new(number).ok_or(MapError::InvalidNumber(number))?);
+    }
+
+    let mut board: Board = Default::default();
+    board.init_shape(shape);
+    let land = board.get_land_hexes();
+    let mut tiles = tiles.to_vec();
+    for _ in 0..MAX_ATTEMPTS {
+        shuffle(random, &mut tiles);
+        shuffle(random, &mut tokens);
+        let mut tokens = tokens.iter();
+        let mut spots = vec![];
+        for (&(row, column), &tile) in land.iter().zip(tiles.iter()) {
+            let token = if tile.is_productive() {
+                spots.push((row, column));
+                tokens.next().cloned()
+            } else {
+                None
+            };
+            board.set_terrain(row, column, tile, token);
+        }
+        if options.repair {
+            repair(&mut board, &spots, random, options);
+        }
+        if get_badness(&board, options) == 0 {
+            return Ok(board);
+        }
+    }
+    Err(MapError::Unsatisfiable)
+}
+
+// A base game map on the standard board.
+// Fails only if the options can't be kept to, which the default ones always can.
+pub fn generate_base_map(seed: u64, options: &MapOptions) -> Result<Board, MapError> {
+    generate_map(&Shape::standard(), &BASE_TILES, &BASE_NUMBERS, seed, options)
+}
+
+// Whether the number tokens on the board keep to the options.
+pub fn keeps_to(board: &Board, options: &MapOptions) -> bool {
+    get_badness(board, options) == 0
+}
+
+// The number of pairs of neighboring hexes whose tokens break the rules of the options.
+// Each pair is counted from both sides.
+fn count_violations(board: &Board, options: &MapOptions) -> u32 {
+    let mut violations = 0;
+    for (row, column) in board.get_land_hexes() {
+        let token = match board.get_token(row, column) {
+            Some(token) => token,
+            None => continue,
+        };
+        let neighbors = board.get_hex_neighbors(row as isize, column as isize)
+            .expect("land hexes are on the board");
+        for (other_row, other_column) in neighbors {
+            let other = match board.get_token(other_row, other_column) {
+                Some(other) => other,
+                None => continue,
+            };
+            if options.separate_red_numbers && token.is_red() && other.is_red() ||
+                options.separate_equal_numbers && token == other {
+                violations += 1;
+            }
+        }
+    }
+    violations
+}
+
+// How far the board is from keeping to the options. Zero means it keeps to them.
+fn get_badness(board: &Board, options: &MapOptions) -> u32 {
+    let excess = options.fairness.map_or(0, |thresholds| analyze(board).get_excess(&thresholds));
+    count_violations(board, options) + excess
+}
+
+// Swap the tiles or the tokens of two productive hexes, keeping the swap unless it makes the
+// board worse.
+fn repair(board: &mut Board, spots: &[(usize, usize)], random: &mut dyn Randomness,
+          options: &MapOptions) {
+    if spots.len() < 2 {
+        return;
+    }
+    let mut badness = get_badness(board, options);
+    for _ in 0..REPAIR_STEPS {
+        if badness == 0 {
+            return;
+        }
+        let a = spots[random.below(spots.len())];
+        let b = spots[random.below(spots.len())];
+        let swap_tiles = random.below(2) == 0;
+        swap(board, a, b, swap_tiles);
+        let after = get_badness(board, options);
+        if after <= badness {
+            badness = after;
+        } else {
+            swap(board, a, b, swap_tiles);
+        }
+    }
+}
+
+fn swap(board: &mut Board, a: (usize, usize), b: (usize, usize), swap_tiles: bool) {
+    let (tile_a, token_a) = (board.get_tile(a.0, a.1), board.get_token(a.0, a.1));
+    let (tile_b, token_b) = (board.get_tile(b.0, b.1), board.get_token(b.0, b.1));
+    if swap_tiles {
+        board.set_terrain(a.0, a.1, tile_b, token_a);
+        board.set_terrain(b.0, b.1, tile_a, token_b);
+    } else {
+        board.set_terrain(a.0, a.1, tile_a, token_b);
+        board.set_terrain(b.0, b.1, tile_b, token_a);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(board: &Board) -> Vec<(TerrainKind, Option<NumberToken>)> {
+        board.get_land_hexes()
+            .into_iter()
+            .map(|(row, column)| (board.get_tile(row, column), board.get_token(row, column)))
+            .collect()
+    }
+
+    #[test]
+    fn test_base_map_is_fully_populated() {
+        let board = generate_base_map(1, &Default::default()).unwrap();
+        let land = board.get_land_hexes();
+        assert_eq!(BASE_TILES.len(), land.len());
+        for tile in &BASE_TILES {
+            let count = |tiles: Vec<TerrainKind>| tiles.iter().filter(|t| *t == tile).count();
+            assert_eq!(count(BASE_TILES.to_vec()),
+                       count(land.iter().map(|&(row, column)| board.get_tile(row, column))
+                             .collect()));
+        }
+
+        let mut numbers = vec![];
+        for (row, column) in board.get_land_hexes() {
+            let token = board.get_token(row, column);
+            if board.get_tile(row, column) == TerrainKind::Desert {
+                assert_eq!(None, token);
+            } else {
+                numbers.push(token.unwrap().get_number());
+            }
+        }
+        numbers.sort();
+        assert_eq!(BASE_NUMBERS.to_vec(), numbers);
+        for (row, column) in board.get_hexes() {
+            if !board.is_land(row, column) {
+                assert_eq!(TerrainKind::Sea, board.get_tile(row, column));
+            }
+        }
+    }
+
+    #[test]
+    fn test_same_seed_same_map() {
+        let options = MapOptions::balanced();
+        assert_eq!(layout(&generate_base_map(5, &options).unwrap()),
+                   layout(&generate_base_map(5, &options).unwrap()));
+        assert!(layout(&generate_base_map(5, &options).unwrap()) !=
+                layout(&generate_base_map(6, &options).unwrap()));
+    }
+
+    #[test]
+    fn test_options_are_kept() {
+        let options = MapOptions::balanced();
+        for seed in 0..20 {
+            let board = generate_base_map(seed, &options).unwrap();
+            assert!(keeps_to(&board, &options));
+            assert!(analyze(&board).is_within(&options.fairness.unwrap()));
+        }
+    }
+
+    #[test]
+    fn test_fair_maps_without_repair() {
+        let options = MapOptions { repair: false, ..MapOptions::balanced() };
+        assert!(keeps_to(&generate_base_map(3, &options).unwrap(), &options));
+    }
+
+    #[test]
+    fn test_repair_keeps_tokens_on_productive_tiles() {
+        let options = MapOptions::balanced();
+        let board = generate_base_map(11, &options).unwrap();
+        for (row, column) in board.get_land_hexes() {
+            assert_eq!(board.get_tile(row, column).is_productive(),
+                       board.get_token(row, column).is_some());
+        }
+    }
+
+    #[test]
+    fn test_bad_sets() {
+        let shape = Shape::standard();
+        let options = Default::default();
+        assert_eq!(Some(MapError::TileCount { tiles: 18, hexes: 19 }),
+                   generate_map(&shape, &BASE_TILES[1..], &BASE_NUMBERS, 0, &options).err());
+        assert_eq!(Some(MapError::NumberCount { numbers: 17, tiles: 18 }),
+                   generate_map(&shape, &BASE_TILES, &BASE_NUMBERS[1..], 0, &options).err());
+        let mut numbers = BASE_NUMBERS;
+        numbers[0] = 7;
+        assert_eq!(Some(MapError::InvalidNumber(7)),
+                   generate_map(&shape, &BASE_TILES, &numbers, 0, &options).err());
+        let mut tiles = BASE_TILES;
+        tiles[0] = TerrainKind::Sea;
+        let error = generate_map(&shape, &tiles, &BASE_NUMBERS, 0, &options).unwrap_err();
+        assert_eq!(MapError::SeaTile, error);
+        assert_eq!("sea can't be one of the land tiles", error.to_string());
+    }
+
+    #[test]
+    fn test_unsatisfiable() {
+        let shape = Shape::rectangle(1, 2);
+        let tiles = [TerrainKind::Hills, TerrainKind::Forest];
+        let options = MapOptions { fairness: None, repair: false, ..MapOptions::balanced() };
+        assert_eq!(Some(MapError::Unsatisfiable),
+                   generate_map(&shape, &tiles, &[6, 8], 0, &options).err());
+        assert!(generate_map(&shape, &tiles, &[6, 9], 0, &options).is_ok());
+
+        // No base map has every intersection under 5 pips.
+        let thresholds = FairnessThresholds {
+            max_intersection_pips: 4,
+            ..Default::default()
+        };
+        let options = MapOptions {
+            fairness: Some(thresholds),
+            repair: false,
+            ..Default::default()
+        };
+        assert_eq!(Err(MapError::Unsatisfiable), generate_base_map(0, &options));
+    }
+}
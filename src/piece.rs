@@ -0,0 +1,107 @@
+// The pieces players put on the board.
+
+use std::fmt;
+
+// Players are numbered from 0 in turn order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct PlayerId(pub usize);
+
+impl fmt::Display for PlayerId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "player {}", self.0)
+    }
+}
+
+// The kinds of pieces in a player's supply.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PieceKind {
+    Settlement,
+    City,
+    Road,
+}
+
+impl fmt::Display for PieceKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            PieceKind::Settlement => "settlement",
+            PieceKind::City => "city",
+            PieceKind::Road => "road",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BuildingKind {
+    Settlement,
+    City,
+}
+
+// A building on an intersection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Building {
+    pub kind: BuildingKind,
+    pub owner: PlayerId,
+}
+
+impl Building {
+    pub fn settlement(owner: PlayerId) -> Building {
+        Building {
+            kind: BuildingKind::Settlement,
+            owner,
+        }
+    }
+
+    pub fn city(owner: PlayerId) -> Building {
+        Building {
+            kind: BuildingKind::City,
+            owner,
+        }
+    }
+
+    // The number of resources the building collects from each hex it touches.
+    pub fn get_production(&self) -> u32 {
+        match self.kind {
+            BuildingKind::Settlement => 1,
+            BuildingKind::City => 2,
+        }
+    }
+
+    pub fn get_victory_points(&self) -> u32 {
+        match self.kind {
+            BuildingKind::Settlement => 1,
+            BuildingKind::City => 2,
+        }
+    }
+}
+
+// Roads are built on edges with land on some side and ships on edges with sea on some side.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RoadKind {
+    Road,
+    Ship,
+}
+
+// A road or ship on an edge.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Road {
+    pub kind: RoadKind,
+    pub owner: PlayerId,
+}
+
+impl Road {
+    #[allow(clippy::self_named_constructors)]
+    pub fn road(owner: PlayerId) -> Road {
+        Road {
+            kind: RoadKind::Road,
+            owner,
+        }
+    }
+
+    pub fn ship(owner: PlayerId) -> Road {
+        Road {
+            kind: RoadKind::Ship,
+            owner,
+        }
+    }
+}
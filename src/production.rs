@@ -0,0 +1,143 @@
+// Handing out resources when a number is rolled.
+//
+// Every settlement on a hex with the number collects one card of its resource and every city
+// two, except on the hex with the robber.
+// If the bank can't pay everyone what they are owed of a resource, nobody gets any of it, unless
+// only one player is owed it. That player gets whatever the bank has left.
+
+use bank::Bank;
+use board::Board;
+use hand::Hand;
+use terrain::{Resource, RESOURCES};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProductionReport {
+    pub roll: u8,
+    // The cards each player collected, indexed by player.
+    pub received: Vec<Hand>,
+    // The resources the bank couldn't pay in full.
+    pub shortages: Vec<Resource>,
+    // The hex with the number that the robber kept from producing.
+    pub blocked: Option<(usize, usize)>,
+}
+
+// The cards each of the players is owed for the roll, ignoring the bank.
+pub fn get_owed(board: &Board, roll: u8, players: usize) -> Vec<Hand> {
+    let mut owed = vec![Hand::default(); players];
+    for (row, column) in board.get_hexes_with_number(roll) {
+        if board.get_robber() == Some((row, column)) {
+            continue;
+        }
+        let resource = match board.get_tile(row, column).resource() {
+            Some(resource) => resource,
+            None => continue,
+        };
+        for (_, building) in board.get_hex_buildings(row, column) {
+            owed[building.owner.0].add(resource, building.get_production());
+        }
+    }
+    owed
+}
+
+// Pay out the roll from the bank under the shortage rule.
+pub fn produce(board: &Board, bank: &mut Bank, roll: u8, players: usize) -> ProductionReport {
+    let mut received = get_owed(board, roll, players);
+    let mut shortages = vec![];
+    for &resource in &RESOURCES {
+        let total: u32 = received.iter().map(|hand| hand.get(resource)).sum();
+        if total <= bank.get(resource) {
+            continue;
+        }
+        shortages.push(resource);
+        let owed: Vec<usize> = (0..players).filter(|&p| received[p].get(resource) > 0).collect();
+        for &player in &owed {
+            let count = received[player].get(resource);
+            received[player].remove(resource, count);
+        }
+        if owed.len() == 1 {
+            received[owed[0]].add(resource, bank.get(resource));
+        }
+    }
+    for hand in &received {
+        bank.pay(hand);
+    }
+    let blocked = board.get_robber().filter(|&(row, column)| {
+        board.get_token(row, column).map(|token| token.get_number()) == Some(roll)
+    });
+    ProductionReport {
+        roll,
+        received,
+        shortages,
+        blocked,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use piece::{Building, PlayerId};
+    use shape::Shape;
+    use terrain::{NumberToken, TerrainKind};
+
+    // A hills and a forest with a 6 side by side, with a city of player 0 on the north corner
+    // of the hills and a settlement of player 1 on the north corner of the forest.
+    fn make_board() -> Board {
+        let mut board: Board = Default::default();
+        board.init_shape(&Shape::parse("L L").unwrap());
+        board.set_terrain(0, 0, TerrainKind::Hills, NumberToken::new(6));
+        board.set_terrain(0, 1, TerrainKind::Forest, NumberToken::new(6));
+        let first = board.get_hex_intersections(0, 0)[0];
+        let second = board.get_hex_intersections(0, 1)[0];
+        board.set_building(first, Some(Building::city(PlayerId(0))));
+        board.set_building(second, Some(Building::settlement(PlayerId(1))));
+        board
+    }
+
+    #[test]
+    fn test_produce() {
+        let board = make_board();
+        let mut bank: Bank = Default::default();
+        let report = produce(&board, &mut bank, 6, 2);
+        assert_eq!(Hand::single(Resource::Brick, 2), report.received[0]);
+        assert_eq!(Hand::single(Resource::Lumber, 1), report.received[1]);
+        assert_eq!(17, bank.get(Resource::Brick));
+        assert!(report.shortages.is_empty());
+        assert_eq!(None, report.blocked);
+
+        let report = produce(&board, &mut bank, 8, 2);
+        assert!(report.received.iter().all(|hand| hand.is_empty()));
+    }
+
+    #[test]
+    fn test_robber_blocks_production() {
+        let mut board = make_board();
+        board.set_robber(0, 0);
+        let mut bank: Bank = Default::default();
+        let report = produce(&board, &mut bank, 6, 2);
+        assert!(report.received[0].is_empty());
+        assert_eq!(1, report.received[1].total());
+        assert_eq!(Some((0, 0)), report.blocked);
+    }
+
+    #[test]
+    fn test_shortage() {
+        let mut board = make_board();
+        board.set_terrain(0, 1, TerrainKind::Hills, NumberToken::new(6));
+        let mut bank: Bank = Default::default();
+        bank.pay(&Hand::single(Resource::Brick, 17));
+        // Both players are owed brick and the bank can't pay both, so neither gets any.
+        let report = produce(&board, &mut bank, 6, 2);
+        assert!(report.received.iter().all(|hand| hand.is_empty()));
+        assert_eq!(vec![Resource::Brick], report.shortages);
+        assert_eq!(2, bank.get(Resource::Brick));
+
+        // Only one player is owed brick, so that player gets what is left.
+        let second = board.get_hex_intersections(0, 1)[0];
+        board.set_building(second, None);
+        bank.pay(&Hand::single(Resource::Brick, 1));
+        let report = produce(&board, &mut bank, 6, 2);
+        assert_eq!(Hand::single(Resource::Brick, 1), report.received[0]);
+        assert_eq!(vec![Resource::Brick], report.shortages);
+        assert_eq!(0, bank.get(Resource::Brick));
+    }
+}
@@ -0,0 +1,321 @@
+// Randomness.
+//
+// Everything random in a game, from the map and the deck to the dice and the robber's steals,
+// comes from a Randomness, so that a game can be played again exactly. The default is a small
+// seeded random number generator, so that a seed always gives the same game. Tests and replays
+// can script the dice and the other choices instead.
+// A game is saved with its generator, so only the generators here can be saved.
+
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::error::Error as StdError;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Error, Serialize, Serializer};
+
+pub trait Randomness: fmt::Debug {
+    // A number in 0..bound.
+    fn below(&mut self, bound: usize) -> usize;
+
+    // The total of two dice.
+    fn roll_dice(&mut self) -> u8 {
+        (self.below(6) + self.below(6) + 2) as u8
+    }
+
+    fn box_clone(&self) -> Box<dyn Randomness>;
+
+    // Numbers that tell apart generators that would give different numbers from here on.
+    fn get_state(&self) -> Vec<u64>;
+
+    // The generator as it is saved with a game, if it can be saved.
+    fn save(&self) -> Option<SavedRandomness> {
+        None
+    }
+}
+
+// The generators that can be saved.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SavedRandomness {
+    Seeded(Random),
+    Scripted(Scripted),
+}
+
+impl SavedRandomness {
+    pub fn into_randomness(self) -> Box<dyn Randomness> {
+        match self {
+            SavedRandomness::Seeded(random) => Box::new(random),
+            SavedRandomness::Scripted(scripted) => Box::new(scripted),
+        }
+    }
+}
+
+// A boxed Randomness that can be cloned and compared, for keeping in a game state.
+#[derive(Debug)]
+pub struct RandomSource(Box<dyn Randomness>);
+
+impl RandomSource {
+    pub fn new(random: Box<dyn Randomness>) -> RandomSource {
+        RandomSource(random)
+    }
+}
+
+impl Deref for RandomSource {
+    type Target = dyn Randomness;
+
+    fn deref(&self) -> &(dyn Randomness + 'static) {
+        &*self.0
+    }
+}
+
+impl DerefMut for RandomSource {
+    fn deref_mut(&mut self) -> &mut (dyn Randomness + 'static) {
+        &mut *self.0
+    }
+}
+
+impl Clone for RandomSource {
+    fn clone(&self) -> RandomSource {
+        RandomSource(self.0.box_clone())
+    }
+}
+
+impl PartialEq for RandomSource {
+    fn eq(&self, other: &RandomSource) -> bool {
+        self.get_state() == other.get_state()
+    }
+}
+
+impl Eq for RandomSource {}
+
+impl Serialize for RandomSource {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.save() {
+            Some(saved) => saved.serialize(serializer),
+            None => Err(S::Error::custom(format!("{:?} can't be saved", self.0))),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for RandomSource {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<RandomSource, D::Error> {
+        SavedRandomness::deserialize(deserializer)
+            .map(|saved| RandomSource::new(saved.into_randomness()))
+    }
+}
+
+pub fn shuffle<T>(random: &mut dyn Randomness, items: &mut [T]) {
+    for i in (1..items.len()).rev() {
+        let j = random.below(i + 1);
+        items.swap(i, j);
+    }
+}
+
+// xorshift64* seeded through splitmix64, which is plenty for shuffling tiles.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Random {
+        // Spread the seed out so that nearby seeds give unrelated sequences, and avoid the
+        // all-zero state that xorshift can't leave.
+        let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        Random {
+            state: if z == 0 { 1 } else { z },
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+}
+
+impl Randomness for Random {
+    // Without favoring small numbers.
+    fn below(&mut self, bound: usize) -> usize {
+        assert!(0 < bound);
+        let bound = bound as u64;
+        let zone = u64::MAX - u64::MAX % bound;
+        loop {
+            let x = self.next_u64();
+            if x < zone {
+                return (x % bound) as usize;
+            }
+        }
+    }
+
+    fn box_clone(&self) -> Box<dyn Randomness> {
+        Box::new(self.clone())
+    }
+
+    fn get_state(&self) -> Vec<u64> {
+        vec![self.state]
+    }
+
+    fn save(&self) -> Option<SavedRandomness> {
+        Some(SavedRandomness::Seeded(self.clone()))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScriptError {
+    // A scripted roll that two dice can't make.
+    BadRoll(u8),
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ScriptError::BadRoll(roll) => write!(f, "two dice can't roll {}", roll),
+        }
+    }
+}
+
+impl StdError for ScriptError {}
+
+// Randomness that gives the scripted dice rolls and numbers first, and then falls back on a
+// seeded generator. The rolls are checked when the script is made or loaded, see Script.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "Script")]
+pub struct Scripted {
+    rolls: VecDeque<u8>,
+    numbers: VecDeque<usize>,
+    fallback: Random,
+}
+
+// A script as it was saved, before its rolls are checked.
+#[derive(Deserialize)]
+struct Script {
+    rolls: VecDeque<u8>,
+    numbers: VecDeque<usize>,
+    fallback: Random,
+}
+
+impl TryFrom<Script> for Scripted {
+    type Error = ScriptError;
+
+    fn try_from(script: Script) -> Result<Scripted, ScriptError> {
+        if let Some(&roll) = script.rolls.iter().find(|roll| !(2..=12).contains(*roll)) {
+            return Err(ScriptError::BadRoll(roll));
+        }
+        Ok(Scripted {
+            rolls: script.rolls,
+            numbers: script.numbers,
+            fallback: script.fallback,
+        })
+    }
+}
+
+impl Scripted {
+    // Fails if one of the rolls isn't 2 to 12.
+    pub fn new(seed: u64, rolls: &[u8], numbers: &[usize]) -> Result<Scripted, ScriptError> {
+        Scripted::try_from(Script {
+            rolls: rolls.iter().cloned().collect(),
+            numbers: numbers.iter().cloned().collect(),
+            fallback: Random::new(seed),
+        })
+    }
+}
+
+impl Randomness for Scripted {
+    fn below(&mut self, bound: usize) -> usize {
+        match self.numbers.pop_front() {
+            Some(number) => {
+                assert!(number < bound);
+                number
+            }
+            None => self.fallback.below(bound),
+        }
+    }
+
+    fn roll_dice(&mut self) -> u8 {
+        match self.rolls.pop_front() {
+            Some(roll) => roll,
+            None => self.fallback.roll_dice(),
+        }
+    }
+
+    fn box_clone(&self) -> Box<dyn Randomness> {
+        Box::new(self.clone())
+    }
+
+    fn get_state(&self) -> Vec<u64> {
+        let mut state = self.fallback.get_state();
+        state.push(self.rolls.len() as u64);
+        state.extend(self.rolls.iter().map(|&roll| u64::from(roll)));
+        state.extend(self.numbers.iter().map(|&number| number as u64));
+        state
+    }
+
+    fn save(&self) -> Option<SavedRandomness> {
+        Some(SavedRandomness::Scripted(self.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json;
+
+    #[test]
+    fn test_same_seed_same_sequence() {
+        let mut a = Random::new(42);
+        let mut b = Random::new(42);
+        let mut c = Random::new(43);
+        let xs: Vec<u64> = (0..8).map(|_| a.next_u64()).collect();
+        let ys: Vec<u64> = (0..8).map(|_| b.next_u64()).collect();
+        let zs: Vec<u64> = (0..8).map(|_| c.next_u64()).collect();
+        assert_eq!(xs, ys);
+        assert!(xs != zs);
+    }
+
+    #[test]
+    fn test_below() {
+        let mut random = Random::new(0);
+        let mut seen = [0; 6];
+        for _ in 0..6000 {
+            seen[random.below(6)] += 1;
+        }
+        assert!(seen.iter().all(|&n| n > 800 && n < 1200));
+    }
+
+    #[test]
+    fn test_shuffle_is_a_permutation() {
+        let mut random = Random::new(7);
+        let mut items: Vec<usize> = (0..20).collect();
+        shuffle(&mut random, &mut items);
+        assert!(items != (0..20).collect::<Vec<usize>>());
+        items.sort();
+        assert_eq!((0..20).collect::<Vec<usize>>(), items);
+    }
+
+    #[test]
+    fn test_scripted() {
+        let mut scripted = Scripted::new(3, &[7, 12], &[4]).unwrap();
+        let mut random = Random::new(3);
+        assert_eq!(7, scripted.roll_dice());
+        assert_eq!(12, scripted.roll_dice());
+        assert_eq!(4, scripted.below(5));
+        assert_eq!(random.roll_dice(), scripted.roll_dice());
+        let source = RandomSource::new(Box::new(scripted));
+        assert!(source == source.clone());
+
+        assert_eq!(Err(ScriptError::BadRoll(13)), Scripted::new(3, &[7, 13], &[]));
+        assert_eq!(Err(ScriptError::BadRoll(1)), Scripted::new(3, &[1], &[]));
+        let saved = SavedRandomness::Scripted(Scripted::new(3, &[2, 12], &[]).unwrap());
+        let json = serde_json::to_string(&saved).unwrap();
+        assert!(serde_json::from_str::<SavedRandomness>(&json).is_ok());
+        let json = json.replace("[2,12]", "[2,0]");
+        let error = serde_json::from_str::<SavedRandomness>(&json).unwrap_err();
+        assert!(error.to_string().contains("two dice can't roll 0"), "{}", error);
+    }
+}
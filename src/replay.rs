@@ -0,0 +1,355 @@
+// Game logs, and replaying them.
+//
+// A log starts with a header holding everything the game was started from, and then has one
+// entry per step taken, in order. Entries are only ever added, so a log can be written out a
+// line at a time as the game goes: the header as a line of JSON, then each entry as a line.
+// A recorder plays the game from the header and logs every step taken through it, undos and
+// redos included, along with what came of it, like the dice rolled or the card drawn. Every so
+// often an entry also records a hash of the state after it.
+//
+// Replaying the log starts the game again from the header and takes the steps again. Since
+// the randomness is seeded, the game should come out the same. The replay stops at the first
+// step that comes out differently or is refused, or at the first checkpoint whose hash is
+// different, so a bug shows up close to where it happened.
+
+use std::error::Error;
+use std::fmt;
+
+use serde_json;
+
+use action::{Action, Outcome};
+use board::Board;
+use error::GameError;
+use game::GameState;
+use history::Record;
+use piece::PlayerId;
+use rules::Rules;
+use save::{to_json, LoadError};
+use snapshot::{checksum, to_snapshot};
+
+// How many entries apart the state hashes are recorded.
+pub const CHECKPOINT_INTERVAL: usize = 10;
+
+// Everything the game was started from.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Header {
+    pub rules: Rules,
+    pub seed: u64,
+    pub players: usize,
+    // The board before the robber was put on it.
+    pub board: Board,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Step {
+    Apply(Record),
+    Undo,
+    Redo,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    pub step: Step,
+    // The hash of the state after the step, at checkpoints.
+    pub hash: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameLog {
+    header: Header,
+    entries: Vec<Entry>,
+}
+
+// Why a replay stopped. Positions count the entries taken, so the entry at fault is the one
+// just before the position.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReplayError {
+    // The log has fewer entries than asked for.
+    OutOfRange(usize),
+    Refused(usize, GameError),
+    OutcomeDiffers { position: usize, recorded: Box<Outcome>, replayed: Box<Outcome> },
+    HashDiffers { position: usize, recorded: u64, replayed: u64 },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ReplayError::OutOfRange(position) =>
+                write!(f, "the log has fewer than {} entries", position),
+            ReplayError::Refused(position, ref error) =>
+                write!(f, "entry {} was refused: {}", position - 1, error),
+            ReplayError::OutcomeDiffers { position, ref recorded, ref replayed } =>
+                write!(f, "entry {} came out as {:?} instead of {:?}",
+                       position - 1, replayed, recorded),
+            ReplayError::HashDiffers { position, recorded, replayed } =>
+                write!(f, "the state after entry {} hashes to {:x} instead of {:x}",
+                       position - 1, replayed, recorded),
+        }
+    }
+}
+
+impl Error for ReplayError {}
+
+// A hash of everything in the state but its history, the same for states that are equal.
+// Only for games started from a log, whose randomness is seeded and so can be saved.
+fn hash_state(state: &GameState) -> u64 {
+    let bytes = to_snapshot(state).expect("a logged game is seeded, so it can be saved");
+    checksum(&bytes)
+}
+
+impl GameLog {
+    // Start a log for a game to be played on the board.
+    pub fn new(board: Board, players: usize, seed: u64, rules: Rules) -> GameLog {
+        GameLog {
+            header: Header { rules, seed, players, board },
+            entries: vec![],
+        }
+    }
+
+    pub fn get_header(&self) -> &Header {
+        &self.header
+    }
+
+    pub fn get_entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    // The game as it was before any step.
+    pub fn start(&self) -> GameState {
+        let header = &self.header;
+        GameState::with_rules(header.board.clone(), header.players, header.seed, header.rules)
+    }
+
+    // The game after the given number of entries.
+    pub fn replay(&self, position: usize) -> Result<GameState, ReplayError> {
+        let mut replayer = Replayer::new(self);
+        replayer.seek(position)?;
+        Ok(replayer.state)
+    }
+
+    // One line of JSON for the header and one for each entry.
+    pub fn to_lines(&self) -> String {
+        let mut lines = to_json(&self.header).expect("a header can always be saved");
+        for entry in &self.entries {
+            lines.push('\n');
+            lines.push_str(&to_json(entry).expect("an entry can always be saved"));
+        }
+        lines
+    }
+
+    pub fn from_lines(lines: &str) -> Result<GameLog, LoadError> {
+        let mut lines = lines.lines().filter(|line| !line.trim().is_empty());
+        let header = serde_json::from_str(lines.next().unwrap_or(""))?;
+        let mut entries = vec![];
+        for line in lines {
+            entries.push(serde_json::from_str(line)?);
+        }
+        Ok(GameLog { header, entries })
+    }
+}
+
+// A game being played and logged. The recorder keeps the game, started from the log's header,
+// so that everything logged can be replayed.
+pub struct Recorder {
+    log: GameLog,
+    state: GameState,
+}
+
+impl Recorder {
+    pub fn new(board: Board, players: usize, seed: u64, rules: Rules) -> Recorder {
+        let log = GameLog::new(board, players, seed, rules);
+        let state = log.start();
+        Recorder { log, state }
+    }
+
+    pub fn get_log(&self) -> &GameLog {
+        &self.log
+    }
+
+    pub fn get_state(&self) -> &GameState {
+        &self.state
+    }
+
+    pub fn into_log(self) -> GameLog {
+        self.log
+    }
+
+    // Apply the action to the game and log it if it was taken.
+    pub fn apply(&mut self, player: PlayerId, action: &Action) -> Result<Outcome, GameError> {
+        let outcome = self.state.apply(player, action)?;
+        let record = self.state.get_history().get_last()
+            .expect("an applied action is in the history")
+            .clone();
+        self.push(Step::Apply(record));
+        Ok(outcome)
+    }
+
+    pub fn undo(&mut self) -> Result<(), GameError> {
+        self.state.undo()?;
+        self.push(Step::Undo);
+        Ok(())
+    }
+
+    pub fn redo(&mut self) -> Result<(), GameError> {
+        self.state.redo()?;
+        self.push(Step::Redo);
+        Ok(())
+    }
+
+    fn push(&mut self, step: Step) {
+        let entries = &mut self.log.entries;
+        let hash = if (entries.len() + 1).is_multiple_of(CHECKPOINT_INTERVAL) {
+            Some(hash_state(&self.state))
+        } else {
+            None
+        };
+        entries.push(Entry { step, hash });
+    }
+}
+
+// Steps through a log, checking each step against what was recorded.
+pub struct Replayer<'a> {
+    log: &'a GameLog,
+    state: GameState,
+    position: usize,
+}
+
+impl<'a> Replayer<'a> {
+    pub fn new(log: &'a GameLog) -> Replayer<'a> {
+        Replayer {
+            log,
+            state: log.start(),
+            position: 0,
+        }
+    }
+
+    pub fn get_state(&self) -> &GameState {
+        &self.state
+    }
+
+    // The number of entries taken.
+    pub fn get_position(&self) -> usize {
+        self.position
+    }
+
+    // Take the next entry. After an error the state is wherever the step left it.
+    pub fn step(&mut self) -> Result<(), ReplayError> {
+        let entry = self.log.entries.get(self.position)
+            .ok_or(ReplayError::OutOfRange(self.position + 1))?;
+        self.position += 1;
+        let position = self.position;
+        match entry.step {
+            Step::Apply(ref record) => {
+                let outcome = self.state.apply(record.player, &record.action)
+                    .map_err(|error| ReplayError::Refused(position, error))?;
+                if outcome != record.outcome {
+                    return Err(ReplayError::OutcomeDiffers {
+                        position,
+                        recorded: Box::new(record.outcome.clone()),
+                        replayed: Box::new(outcome),
+                    });
+                }
+            }
+            Step::Undo => self.state.undo().map_err(|error| ReplayError::Refused(position, error))?,
+            Step::Redo => self.state.redo().map_err(|error| ReplayError::Refused(position, error))?,
+        }
+        if let Some(recorded) = entry.hash {
+            let replayed = hash_state(&self.state);
+            if replayed != recorded {
+                return Err(ReplayError::HashDiffers { position, recorded, replayed });
+            }
+        }
+        Ok(())
+    }
+
+    // Go to the game after the given number of entries, starting over to go back.
+    pub fn seek(&mut self, position: usize) -> Result<&GameState, ReplayError> {
+        if position > self.log.entries.len() {
+            return Err(ReplayError::OutOfRange(position));
+        }
+        if position < self.position {
+            *self = Replayer::new(self.log);
+        }
+        while self.position < position {
+            self.step()?;
+        }
+        Ok(&self.state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mapgen::generate_base_map;
+    use random::{Random, Randomness};
+
+    // Play random legal actions with a few undos and redos, keeping the state after each entry.
+    fn play(steps: usize) -> (GameLog, Vec<GameState>) {
+        let board = generate_base_map(0, &Default::default()).unwrap();
+        let mut recorder = Recorder::new(board, 3, 7, Default::default());
+        let mut states = vec![recorder.get_state().snapshot()];
+        let mut random = Random::new(1);
+        while recorder.get_log().get_entries().len() < steps {
+            let state = recorder.get_state();
+            match random.below(10) {
+                0 if state.check_undo().is_ok() => recorder.undo().unwrap(),
+                1 if state.get_history().can_redo() => recorder.redo().unwrap(),
+                _ => {
+                    let legal = state.legal_actions();
+                    let (player, action) = legal[random.below(legal.len())];
+                    recorder.apply(player, &action).unwrap();
+                }
+            }
+            states.push(recorder.get_state().snapshot());
+        }
+        (recorder.into_log(), states)
+    }
+
+    #[test]
+    fn test_replay() {
+        let (log, states) = play(120);
+        assert!(log.get_entries().iter().any(|entry| entry.step == Step::Undo));
+        assert_eq!(12, log.get_entries().iter().filter(|entry| entry.hash.is_some()).count());
+        assert_eq!(states[0], log.replay(0).unwrap().snapshot());
+        assert_eq!(states[57], log.replay(57).unwrap().snapshot());
+        assert_eq!(states[120], log.replay(120).unwrap().snapshot());
+        assert_eq!(Err(ReplayError::OutOfRange(121)), log.replay(121));
+
+        let mut replayer = Replayer::new(&log);
+        replayer.seek(80).unwrap();
+        assert_eq!(states[30], replayer.seek(30).unwrap().snapshot());
+        replayer.step().unwrap();
+        assert_eq!(31, replayer.get_position());
+        assert_eq!(states[31], replayer.get_state().snapshot());
+    }
+
+    #[test]
+    fn test_lines() {
+        let (log, _) = play(25);
+        let lines = log.to_lines();
+        assert_eq!(26, lines.lines().count());
+        assert_eq!(log, GameLog::from_lines(&lines).unwrap());
+        assert!(GameLog::from_lines("").is_err());
+    }
+
+    #[test]
+    fn test_divergence() {
+        let (log, _) = play(60);
+        let mut wrong = log.clone();
+        wrong.entries[39].hash = Some(0);
+        match wrong.replay(60) {
+            Err(ReplayError::HashDiffers { position: 40, recorded: 0, .. }) => {}
+            result => panic!("{:?}", result),
+        }
+        assert!(wrong.replay(39).is_ok());
+
+        // Another seed rolls other dice sooner or later.
+        let mut reseeded = log.clone();
+        reseeded.header.seed = 8;
+        match reseeded.replay(60) {
+            Err(ReplayError::OutcomeDiffers { .. }) | Err(ReplayError::HashDiffers { .. }) |
+            Err(ReplayError::Refused(..)) => {}
+            result => panic!("{:?}", result),
+        }
+    }
+}
@@ -0,0 +1,22 @@
+// The settings a game is played with.
+
+use history::UndoPolicy;
+
+// The usual number of victory points to win.
+pub const VICTORY_TARGET: u32 = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Rules {
+    // The victory points the player whose turn it is needs to win.
+    pub victory_target: u32,
+    pub undo: UndoPolicy,
+}
+
+impl Default for Rules {
+    fn default() -> Rules {
+        Rules {
+            victory_target: VICTORY_TARGET,
+            undo: Default::default(),
+        }
+    }
+}
@@ -0,0 +1,199 @@
+// Saving boards and games.
+//
+// Boards and game states are saved as JSON in the shape serde derives from their fields, so the
+// shape only changes when the fields do. A game is saved with its random number generator, so a
+// loaded game rolls the same dice, but without its history, so it can't undo what came before.
+// Loading checks that the sizes in the save fit together before anything uses them.
+
+use std::error::Error;
+use std::fmt;
+
+use serde::Serialize;
+use serde_json::{self, Value};
+
+use board::{Board, LayoutError};
+use game::GameState;
+use snapshot::SnapshotError;
+
+#[derive(Debug)]
+pub enum LoadError {
+    // Not JSON of the right shape, or a board whose layout doesn't fit its height and width.
+    Json(serde_json::Error),
+    // A game whose players don't fit together.
+    Layout(LayoutError),
+    Snapshot(SnapshotError),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LoadError::Json(ref error) => write!(f, "invalid save: {}", error),
+            LoadError::Layout(error) => write!(f, "invalid save: {}", error),
+            LoadError::Snapshot(error) => write!(f, "invalid save: {}", error),
+        }
+    }
+}
+
+impl Error for LoadError {}
+
+impl From<serde_json::Error> for LoadError {
+    fn from(error: serde_json::Error) -> LoadError {
+        LoadError::Json(error)
+    }
+}
+
+impl From<LayoutError> for LoadError {
+    fn from(error: LayoutError) -> LoadError {
+        LoadError::Layout(error)
+    }
+}
+
+impl From<SnapshotError> for LoadError {
+    fn from(error: SnapshotError) -> LoadError {
+        LoadError::Snapshot(error)
+    }
+}
+
+// Fails only for a game whose randomness can't be saved.
+pub fn to_json<T: Serialize>(value: &T) -> Result<String, serde_json::Error> {
+    serde_json::to_string(value)
+}
+
+pub fn board_from_json(json: &str) -> Result<Board, LoadError> {
+    Ok(serde_json::from_str(json)?)
+}
+
+pub fn game_from_json(json: &str) -> Result<GameState, LoadError> {
+    let state: GameState = serde_json::from_str(json)?;
+    state.check_layout()?;
+    Ok(state)
+}
+
+// Load from the tree the JSON parses to, like a snapshot decodes to.
+pub fn board_from_value(value: Value) -> Result<Board, LoadError> {
+    Ok(serde_json::from_value(value)?)
+}
+
+pub fn game_from_value(value: Value) -> Result<GameState, LoadError> {
+    let state: GameState = serde_json::from_value(value)?;
+    state.check_layout()?;
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use board::IntersectionKind;
+    use mapgen::generate_base_map;
+    use piece::PlayerId;
+    use random::Scripted;
+    use serde_json::json;
+
+    #[test]
+    fn test_board_round_trip() {
+        let mut board = generate_base_map(0, &Default::default()).unwrap();
+        board.set_robber(2, 2);
+        let json = to_json(&board).unwrap();
+        assert_eq!(board, board_from_json(&json).unwrap());
+
+        let value: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(7, value["height"]);
+        assert_eq!(9, value["mem_width"]);
+        assert_eq!(json!([2, 2]), value["robber"]);
+    }
+
+    #[test]
+    fn test_board_layout_is_checked() {
+        let board = generate_base_map(0, &Default::default()).unwrap();
+        let value = serde_json::to_value(&board).unwrap();
+        let load = |change: &dyn Fn(&mut Value)| {
+            let mut value = value.clone();
+            change(&mut value);
+            board_from_json(&value.to_string())
+        };
+        assert!(load(&|_| {}).is_ok());
+        let error = load(&|value| value["right_edge_size"] = json!(63)).unwrap_err();
+        assert!(error.to_string().contains("right_edge_size"), "{}", error);
+        let error = load(&|value| value["width"] = json!(8)).unwrap_err();
+        assert!(error.to_string().contains("mem_width"), "{}", error);
+        let error = load(&|value| { value["edges"].as_array_mut().unwrap().pop(); })
+            .unwrap_err();
+        assert!(error.to_string().contains("edges"), "{}", error);
+        assert!(load(&|value| value["height"] = json!(0)).is_err());
+        assert!(load(&|value| value["robber"] = json!([0, 0])).is_err());
+        assert!(load(&|value| value["terrains"][10]["token"] = json!(7)).is_err());
+    }
+
+    #[test]
+    fn test_game_round_trip() {
+        let board = generate_base_map(0, &Default::default()).unwrap();
+        let mut state = GameState::new(board, 3, 0);
+        let (player, action) = state.legal_actions()[0];
+        state.apply(player, &action).unwrap();
+        let json = to_json(&state).unwrap();
+        let mut loaded = game_from_json(&json).unwrap();
+        assert_eq!(state.snapshot(), loaded);
+        assert!(loaded.get_history().get_records().is_empty());
+
+        // The loaded game goes on exactly like the saved one, dice included.
+        for _ in 0..60 {
+            let (player, action) = match state.legal_actions().last() {
+                Some(&legal) => legal,
+                None => break,
+            };
+            assert_eq!(state.apply(player, &action), loaded.apply(player, &action));
+        }
+        assert_eq!(state.snapshot(), loaded.snapshot());
+    }
+
+    #[test]
+    fn test_game_layout_is_checked() {
+        let board = generate_base_map(0, &Default::default()).unwrap();
+        let random = Scripted::new(0, &[8, 6], &[1]).unwrap();
+        let state = GameState::with_randomness(board, 2, Default::default(), Box::new(random));
+        let value = serde_json::to_value(&state).unwrap();
+        assert_eq!(state, game_from_json(&value.to_string()).unwrap());
+
+        let mut wrong = value.clone();
+        wrong["discards"] = json!([0, 0, 0]);
+        match game_from_json(&wrong.to_string()) {
+            Err(LoadError::Layout(LayoutError::WrongSize { name: "discards", .. })) => {}
+            result => panic!("{:?}", result),
+        }
+        let mut wrong = value.clone();
+        wrong["current"] = json!(2);
+        assert!(game_from_json(&wrong.to_string()).is_err());
+        assert!(game_from_json("{}").is_err());
+
+        // Every piece has to belong to a player in the game.
+        let board = state.get_board();
+        let land = board.get_hex_intersections(3, 3)[0];
+        let mut wrong = value.clone();
+        wrong["board"]["intersections"][land]["building"] = json!({"kind": "City", "owner": 2});
+        match game_from_json(&wrong.to_string()) {
+            Err(LoadError::Layout(LayoutError::NoSuchPlayer(PlayerId(2)))) => {}
+            result => panic!("{:?}", result),
+        }
+        let edge = board.get_hex_edges(3, 3)[0];
+        let mut wrong = value.clone();
+        wrong["board"]["edges"][edge]["road"] = json!({"kind": "Road", "owner": 5});
+        match game_from_json(&wrong.to_string()) {
+            Err(LoadError::Layout(LayoutError::NoSuchPlayer(PlayerId(5)))) => {}
+            result => panic!("{:?}", result),
+        }
+
+        // The kinds are derived from the terrains, and pieces have to fit them.
+        let mut wrong = value.clone();
+        wrong["board"]["intersections"][land]["kind"] = json!("Sea");
+        let error = game_from_json(&wrong.to_string()).unwrap_err();
+        assert!(error.to_string().contains("wrong kind"), "{}", error);
+        let sea = (0..board.get_intersection_size())
+            .find(|&corner| board.get_intersection_kind(corner) == IntersectionKind::Sea)
+            .unwrap();
+        let mut wrong = value.clone();
+        wrong["board"]["intersections"][sea]["building"] =
+            json!({"kind": "Settlement", "owner": 0});
+        let error = game_from_json(&wrong.to_string()).unwrap_err();
+        assert!(error.to_string().contains("not on land"), "{}", error);
+    }
+}
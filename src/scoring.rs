@@ -0,0 +1,125 @@
+// Victory points.
+//
+// Settlements are worth one point and cities two. The longest road and the largest army are
+// worth two each. The largest army goes to the first player to play three knights, and then to
+// whoever has played strictly more. Victory point cards are worth one each, but the other
+// players can't see them until the game is won, so the public score leaves them out.
+
+use board::Board;
+use devcard::DevCard;
+use game::Player;
+use piece::{BuildingKind, PlayerId};
+
+pub const LARGEST_ARMY_MIN: u32 = 3;
+pub const LONGEST_ROAD_POINTS: u32 = 2;
+pub const LARGEST_ARMY_POINTS: u32 = 2;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Score {
+    pub settlements: u32,
+    pub cities: u32,
+    pub longest_road: bool,
+    pub largest_army: bool,
+    pub victory_point_cards: u32,
+}
+
+impl Score {
+    // The points everyone can see.
+    pub fn get_public(&self) -> u32 {
+        let mut points = self.settlements + 2 * self.cities;
+        if self.longest_road {
+            points += LONGEST_ROAD_POINTS;
+        }
+        if self.largest_army {
+            points += LARGEST_ARMY_POINTS;
+        }
+        points
+    }
+
+    // The points including the hidden victory point cards.
+    pub fn get_total(&self) -> u32 {
+        self.get_public() + self.victory_point_cards
+    }
+}
+
+// Score the player, given who holds the longest road and the largest army.
+// Victory point cards count as soon as they are bought.
+pub fn get_score(board: &Board, player: PlayerId, state: &Player,
+                 longest_road: Option<PlayerId>, largest_army: Option<PlayerId>) -> Score {
+    let mut score = Score {
+        longest_road: longest_road == Some(player),
+        largest_army: largest_army == Some(player),
+        ..Default::default()
+    };
+    for intersection in board.get_buildings(player) {
+        match board.get_building(intersection).map(|building| building.kind) {
+            Some(BuildingKind::Settlement) => score.settlements += 1,
+            Some(BuildingKind::City) => score.cities += 1,
+            None => {}
+        }
+    }
+    score.victory_point_cards = state.dev_cards.iter()
+        .chain(state.new_dev_cards.iter())
+        .filter(|&&card| card == DevCard::VictoryPoint)
+        .count() as u32;
+    score
+}
+
+// Who holds the largest army given the knights played by each player, indexed by player, and
+// who held it before.
+pub fn award_largest_army(holder: Option<PlayerId>, knights: &[u32]) -> Option<PlayerId> {
+    let most = knights.iter().cloned().max().unwrap_or(0);
+    if most < LARGEST_ARMY_MIN {
+        return None;
+    }
+    if let Some(holder) = holder {
+        if knights[holder.0] == most {
+            return Some(holder);
+        }
+    }
+    let leaders: Vec<usize> = (0..knights.len()).filter(|&p| knights[p] == most).collect();
+    if leaders.len() == 1 {
+        Some(PlayerId(leaders[0]))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mapgen::generate_base_map;
+    use piece::Building;
+
+    #[test]
+    fn test_score() {
+        let mut board = generate_base_map(0, &Default::default()).unwrap();
+        let corners = board.get_hex_intersections(3, 3);
+        board.set_building(corners[0], Some(Building::settlement(PlayerId(0))));
+        board.set_building(corners[2], Some(Building::city(PlayerId(0))));
+        board.set_building(corners[4], Some(Building::city(PlayerId(1))));
+        let player = Player {
+            dev_cards: vec![DevCard::VictoryPoint, DevCard::Knight],
+            new_dev_cards: vec![DevCard::VictoryPoint],
+            ..Default::default()
+        };
+        let score = get_score(&board, PlayerId(0), &player, Some(PlayerId(0)), Some(PlayerId(1)));
+        assert_eq!(Score {
+            settlements: 1,
+            cities: 1,
+            longest_road: true,
+            largest_army: false,
+            victory_point_cards: 2,
+        }, score);
+        assert_eq!(5, score.get_public());
+        assert_eq!(7, score.get_total());
+    }
+
+    #[test]
+    fn test_award_largest_army() {
+        assert_eq!(None, award_largest_army(None, &[2, 2]));
+        assert_eq!(Some(PlayerId(1)), award_largest_army(None, &[2, 3]));
+        assert_eq!(Some(PlayerId(1)), award_largest_army(Some(PlayerId(1)), &[3, 3]));
+        assert_eq!(Some(PlayerId(0)), award_largest_army(Some(PlayerId(1)), &[4, 3]));
+    }
+}
@@ -0,0 +1,186 @@
+// Shapes describe which hexes of the grid are used and whether they are land or sea.
+//
+// A shape is written as rows of characters, one row per line.
+// L is a land hex, S is a sea hex, and . is a hex that is not on the board.
+// Spaces are ignored, so the rows can be indented to look like the hex grid.
+// The standard board is written like this
+//
+//       . . . S S S S
+//        . . S L L L S
+//         . S L L L L S
+//          S L L L L L S
+//           S L L L L S .
+//            S L L L S . .
+//             S S S S . . .
+
+use std::error::Error;
+use std::fmt;
+
+// The kinds of hexes.
+// OffBoard means they're not used.
+// Sea means nothing grows on them, but they still count as part of the board.
+// Land means it has a terrain and you can build around it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HexKind {
+    #[default]
+    OffBoard,
+    Sea,
+    Land,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ShapeError {
+    Empty,
+    UnknownHex(char),
+    RaggedRow(usize),
+}
+
+impl fmt::Display for ShapeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ShapeError::Empty => write!(f, "shape has no hexes"),
+            ShapeError::UnknownHex(c) => write!(f, "unknown hex '{}' in shape", c),
+            ShapeError::RaggedRow(row) =>
+                write!(f, "row {} of the shape has a different width from the first row", row),
+        }
+    }
+}
+
+impl Error for ShapeError {}
+
+pub const STANDARD_SHAPE: &str = "
+    . . . S S S S
+     . . S L L L S
+      . S L L L L S
+       S L L L L L S
+        S L L L L S .
+         S L L L S . .
+          S S S S . . .
+";
+
+// The board for 5-6 players has 30 land hexes in rows of 3, 4, 5, 6, 5, 4, 3.
+pub const EXTENDED_SHAPE: &str = "
+    . . . . S S S S
+     . . . S L L L S
+      . . S L L L L S
+       . S L L L L L S
+        S L L L L L L S
+         S L L L L L S .
+          S L L L L S . .
+           S L L L S . . .
+            S S S S . . . .
+";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Shape {
+    height: usize,
+    width: usize,
+    hexes: Vec<HexKind>,
+}
+
+impl Shape {
+    // Make a rectangle of land hexes.
+    pub fn rectangle(height: usize, width: usize) -> Shape {
+        assert!(0 < height);
+        assert!(0 < width);
+        Shape {
+            height,
+            width,
+            hexes: vec![HexKind::Land; height * width],
+        }
+    }
+
+    pub fn standard() -> Shape {
+        Shape::parse(STANDARD_SHAPE).unwrap()
+    }
+
+    pub fn extended() -> Shape {
+        Shape::parse(EXTENDED_SHAPE).unwrap()
+    }
+
+    // Parse a shape written as described at the top of the file.
+    // Blank lines are skipped.
+    pub fn parse(descriptor: &str) -> Result<Shape, ShapeError> {
+        let mut width = 0;
+        let mut height = 0;
+        let mut hexes = vec![];
+        for line in descriptor.lines() {
+            let mut row = vec![];
+            for c in line.chars().filter(|c| !c.is_whitespace()) {
+                let kind = match c {
+                    'L' => HexKind::Land,
+                    'S' => HexKind::Sea,
+                    '.' => HexKind::OffBoard,
+                    _ => return Err(ShapeError::UnknownHex(c)),
+                };
+                row.push(kind);
+            }
+            if row.is_empty() {
+                continue;
+            }
+            if height == 0 {
+                width = row.len();
+            } else if row.len() != width {
+                return Err(ShapeError::RaggedRow(height));
+            }
+            hexes.extend(row);
+            height += 1;
+        }
+        if hexes.iter().all(|&kind| kind == HexKind::OffBoard) {
+            return Err(ShapeError::Empty);
+        }
+        Ok(Shape {
+            height,
+            width,
+            hexes,
+        })
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+
+    pub fn get_hex_kind(&self, row: usize, column: usize) -> HexKind {
+        assert!(row < self.height);
+        assert!(column < self.width);
+        self.hexes[row * self.width + column]
+    }
+
+    pub fn count(&self, kind: HexKind) -> usize {
+        self.hexes.iter().filter(|&&k| k == kind).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_standard_shape() {
+        let shape = Shape::standard();
+        assert_eq!(7, shape.get_height());
+        assert_eq!(7, shape.get_width());
+        assert_eq!(19, shape.count(HexKind::Land));
+        assert_eq!(18, shape.count(HexKind::Sea));
+        assert_eq!(HexKind::OffBoard, shape.get_hex_kind(0, 0));
+        assert_eq!(HexKind::Land, shape.get_hex_kind(3, 3));
+    }
+
+    #[test]
+    fn test_extended_shape() {
+        let shape = Shape::extended();
+        assert_eq!(30, shape.count(HexKind::Land));
+        assert_eq!(22, shape.count(HexKind::Sea));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(Err(ShapeError::Empty), Shape::parse(" \n . .\n"));
+        assert_eq!(Err(ShapeError::UnknownHex('X')), Shape::parse("L X"));
+        assert_eq!(Err(ShapeError::RaggedRow(1)), Shape::parse("L L\n L\n"));
+    }
+}
@@ -0,0 +1,460 @@
+// Compact binary snapshots of boards and games.
+//
+// A snapshot holds the same tree as the JSON save, written more tightly:
+//
+//     magic "STLR" | format version, u16 | the tree | checksum of everything before, u64
+//
+// All numbers are little endian. In the tree, each value starts with a tag byte. Tags from
+// SMALL up are the whole numbers 0 to 255 - SMALL, which covers most of a game. Object keys and
+// strings go through a table that is built as it's read. A string is written out the first
+// time, as the next index in the table followed by its length and bytes, and after that it's
+// written as its index alone. Other whole numbers, lengths and indices are LEB128 varints.
+//
+// Since objects keep their keys, fields added with #[serde(default)] load from older snapshots
+// as they are. To change the shape of a saved type otherwise, bump FORMAT_VERSION and add a
+// migration that rewrites the older shape, so that old snapshots still load.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+use serde::Serialize;
+use serde_json::{self, Map, Number, Value};
+
+use board::Board;
+use game::GameState;
+use save::{board_from_value, game_from_value, LoadError};
+
+pub const MAGIC: [u8; 4] = *b"STLR";
+pub const FORMAT_VERSION: u16 = 1;
+
+// The migrations from each version to the next, starting from version 1.
+const MIGRATIONS: &[fn(&mut Value)] = &[];
+
+const NULL: u8 = 0;
+const FALSE: u8 = 1;
+const TRUE: u8 = 2;
+const NUMBER: u8 = 3;
+const NEGATIVE: u8 = 4;
+const FLOAT: u8 = 5;
+const STRING: u8 = 6;
+const ARRAY: u8 = 7;
+const OBJECT: u8 = 8;
+const SMALL: u8 = 16;
+
+// Saved games are only a few levels deep, so anything deeper is corrupt.
+const MAX_DEPTH: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SnapshotError {
+    NotASnapshot,
+    // The snapshot was written by a newer version of the format, or is corrupt.
+    UnknownVersion(u16),
+    BadChecksum,
+    Truncated,
+    // The checksum matched but the tree doesn't make sense.
+    Corrupt(&'static str),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SnapshotError::NotASnapshot => write!(f, "not a snapshot"),
+            SnapshotError::UnknownVersion(version) =>
+                write!(f, "unknown snapshot format version {}", version),
+            SnapshotError::BadChecksum => write!(f, "the snapshot checksum doesn't match"),
+            SnapshotError::Truncated => write!(f, "the snapshot is cut short"),
+            SnapshotError::Corrupt(reason) => write!(f, "corrupt snapshot: {}", reason),
+        }
+    }
+}
+
+impl Error for SnapshotError {}
+
+// 64-bit FNV-1a.
+pub fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+// Fails only for a game whose randomness can't be saved.
+pub fn to_snapshot<T: Serialize>(value: &T) -> Result<Vec<u8>, serde_json::Error> {
+    Ok(encode(&serde_json::to_value(value)?))
+}
+
+// The snapshot of the tree a value is saved as.
+pub fn encode(value: &Value) -> Vec<u8> {
+    let mut writer = Writer {
+        bytes: MAGIC.to_vec(),
+        strings: HashMap::new(),
+    };
+    writer.bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    writer.write(value);
+    let mut bytes = writer.bytes;
+    let sum = checksum(&bytes);
+    bytes.extend_from_slice(&sum.to_le_bytes());
+    bytes
+}
+
+// The tree in the snapshot, brought up to the current format version.
+pub fn decode(bytes: &[u8]) -> Result<Value, SnapshotError> {
+    if bytes.len() < MAGIC.len() || bytes[..MAGIC.len()] != MAGIC {
+        return Err(SnapshotError::NotASnapshot);
+    }
+    if bytes.len() < MAGIC.len() + 2 + 8 {
+        return Err(SnapshotError::Truncated);
+    }
+    let (body, sum) = bytes.split_at(bytes.len() - 8);
+    let mut sum_bytes = [0; 8];
+    sum_bytes.copy_from_slice(sum);
+    if checksum(body) != u64::from_le_bytes(sum_bytes) {
+        return Err(SnapshotError::BadChecksum);
+    }
+    let version = u16::from_le_bytes([body[4], body[5]]);
+    if version == 0 || version > FORMAT_VERSION {
+        return Err(SnapshotError::UnknownVersion(version));
+    }
+    let mut reader = Reader {
+        bytes: body,
+        position: MAGIC.len() + 2,
+        strings: vec![],
+    };
+    let mut value = reader.read(0)?;
+    if reader.position != body.len() {
+        return Err(SnapshotError::Corrupt("bytes left over"));
+    }
+    migrate(&mut value, version, MIGRATIONS);
+    Ok(value)
+}
+
+// Run the migrations from the version the tree was written with up to the last one.
+fn migrate(value: &mut Value, version: u16, migrations: &[fn(&mut Value)]) {
+    for migration in &migrations[version as usize - 1..] {
+        migration(value);
+    }
+}
+
+pub fn board_from_snapshot(bytes: &[u8]) -> Result<Board, LoadError> {
+    board_from_value(decode(bytes)?)
+}
+
+pub fn game_from_snapshot(bytes: &[u8]) -> Result<GameState, LoadError> {
+    game_from_value(decode(bytes)?)
+}
+
+pub fn snapshot_to_json(bytes: &[u8]) -> Result<String, LoadError> {
+    Ok(serde_json::to_string(&decode(bytes)?)?)
+}
+
+pub fn json_to_snapshot(json: &str) -> Result<Vec<u8>, LoadError> {
+    Ok(encode(&serde_json::from_str(json)?))
+}
+
+struct Writer {
+    bytes: Vec<u8>,
+    // The index of each string written so far.
+    strings: HashMap<String, u64>,
+}
+
+impl Writer {
+    fn write_varint(&mut self, mut n: u64) {
+        while n >= 0x80 {
+            self.bytes.push(n as u8 | 0x80);
+            n >>= 7;
+        }
+        self.bytes.push(n as u8);
+    }
+
+    fn write_string(&mut self, string: &str) {
+        if let Some(&index) = self.strings.get(string) {
+            self.write_varint(index);
+            return;
+        }
+        let index = self.strings.len() as u64;
+        self.strings.insert(string.to_string(), index);
+        self.write_varint(index);
+        self.write_varint(string.len() as u64);
+        self.bytes.extend_from_slice(string.as_bytes());
+    }
+
+    fn write(&mut self, value: &Value) {
+        match *value {
+            Value::Null => self.bytes.push(NULL),
+            Value::Bool(false) => self.bytes.push(FALSE),
+            Value::Bool(true) => self.bytes.push(TRUE),
+            Value::Number(ref number) => {
+                if let Some(n) = number.as_u64() {
+                    if n <= u64::from(u8::MAX - SMALL) {
+                        self.bytes.push(SMALL + n as u8);
+                    } else {
+                        self.bytes.push(NUMBER);
+                        self.write_varint(n);
+                    }
+                } else if let Some(n) = number.as_i64() {
+                    self.bytes.push(NEGATIVE);
+                    self.write_varint(!n as u64);
+                } else {
+                    self.bytes.push(FLOAT);
+                    let n = number.as_f64().unwrap_or_default();
+                    self.bytes.extend_from_slice(&n.to_le_bytes());
+                }
+            }
+            Value::String(ref string) => {
+                self.bytes.push(STRING);
+                self.write_string(string);
+            }
+            Value::Array(ref values) => {
+                self.bytes.push(ARRAY);
+                self.write_varint(values.len() as u64);
+                for value in values {
+                    self.write(value);
+                }
+            }
+            Value::Object(ref map) => {
+                self.bytes.push(OBJECT);
+                self.write_varint(map.len() as u64);
+                for (key, value) in map {
+                    self.write_string(key);
+                    self.write(value);
+                }
+            }
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    strings: Vec<String>,
+}
+
+impl<'a> Reader<'a> {
+    fn read_byte(&mut self) -> Result<u8, SnapshotError> {
+        let byte = *self.bytes.get(self.position).ok_or(SnapshotError::Truncated)?;
+        self.position += 1;
+        Ok(byte)
+    }
+
+    fn read_bytes(&mut self, count: usize) -> Result<&'a [u8], SnapshotError> {
+        if self.bytes.len() - self.position < count {
+            return Err(SnapshotError::Truncated);
+        }
+        let bytes = &self.bytes[self.position..self.position + count];
+        self.position += count;
+        Ok(bytes)
+    }
+
+    fn read_varint(&mut self) -> Result<u64, SnapshotError> {
+        let mut n = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.read_byte()?;
+            n |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+        Err(SnapshotError::Corrupt("varint too long"))
+    }
+
+    // A length, which can't be more than the bytes left since everything takes a byte.
+    fn read_length(&mut self) -> Result<usize, SnapshotError> {
+        let length = self.read_varint()?;
+        if length > (self.bytes.len() - self.position) as u64 {
+            return Err(SnapshotError::Truncated);
+        }
+        Ok(length as usize)
+    }
+
+    fn read_string(&mut self) -> Result<String, SnapshotError> {
+        let index = self.read_varint()?;
+        if let Some(string) = self.strings.get(index as usize) {
+            return Ok(string.clone());
+        }
+        if index != self.strings.len() as u64 {
+            return Err(SnapshotError::Corrupt("string index out of order"));
+        }
+        let length = self.read_length()?;
+        let bytes = self.read_bytes(length)?;
+        let string = String::from_utf8(bytes.to_vec())
+            .map_err(|_| SnapshotError::Corrupt("string isn't UTF-8"))?;
+        self.strings.push(string.clone());
+        Ok(string)
+    }
+
+    fn read(&mut self, depth: usize) -> Result<Value, SnapshotError> {
+        if depth > MAX_DEPTH {
+            return Err(SnapshotError::Corrupt("nested too deep"));
+        }
+        let value = match self.read_byte()? {
+            NULL => Value::Null,
+            FALSE => Value::Bool(false),
+            TRUE => Value::Bool(true),
+            NUMBER => Value::from(self.read_varint()?),
+            NEGATIVE => Value::from(!(self.read_varint()? as i64)),
+            FLOAT => {
+                let mut bytes = [0; 8];
+                bytes.copy_from_slice(self.read_bytes(8)?);
+                let number = Number::from_f64(f64::from_le_bytes(bytes))
+                    .ok_or(SnapshotError::Corrupt("number isn't finite"))?;
+                Value::Number(number)
+            }
+            STRING => Value::String(self.read_string()?),
+            ARRAY => {
+                let length = self.read_length()?;
+                let mut values = Vec::with_capacity(length);
+                for _ in 0..length {
+                    values.push(self.read(depth + 1)?);
+                }
+                Value::Array(values)
+            }
+            OBJECT => {
+                let length = self.read_length()?;
+                let mut map = Map::new();
+                for _ in 0..length {
+                    let key = self.read_string()?;
+                    let value = self.read(depth + 1)?;
+                    map.insert(key, value);
+                }
+                Value::Object(map)
+            }
+            tag if tag >= SMALL => Value::from(tag - SMALL),
+            _ => return Err(SnapshotError::Corrupt("unknown tag")),
+        };
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game::Phase;
+    use hand::Hand;
+    use mapgen::generate_base_map;
+    use piece::{Building, BuildingKind, PlayerId};
+    use save::to_json;
+    use serde_json::json;
+
+    fn make_game() -> GameState {
+        let board = generate_base_map(0, &Default::default()).unwrap();
+        let mut state = GameState::new(board, 4, 0);
+        for _ in 0..30 {
+            let (player, action) = state.legal_actions()[0];
+            state.apply(player, &action).unwrap();
+        }
+        state
+    }
+
+    #[test]
+    fn test_values_round_trip() {
+        let value = json!({
+            "small": [0, 1, 239, 240, 300, 1u64 << 63],
+            "negative": [-1, -129, i64::MIN],
+            "float": 0.5,
+            "strings": ["", "Land", "Land", "ünïcode"],
+            "nested": {"Land": null, "flags": [true, false]},
+        });
+        assert_eq!(value, decode(&encode(&value)).unwrap());
+    }
+
+    #[test]
+    fn test_game_round_trip() {
+        let state = make_game();
+        let bytes = to_snapshot(&state).unwrap();
+        assert_eq!(state.snapshot(), game_from_snapshot(&bytes).unwrap());
+        let json = to_json(&state).unwrap();
+        assert!(bytes.len() * 4 < json.len(), "{} bytes against {}", bytes.len(), json.len());
+
+        // The snapshot holds exactly what the JSON does.
+        let from_snapshot: Value = serde_json::from_str(&snapshot_to_json(&bytes).unwrap())
+            .unwrap();
+        assert_eq!(serde_json::from_str::<Value>(&json).unwrap(), from_snapshot);
+        assert_eq!(bytes, json_to_snapshot(&json).unwrap());
+
+        let board = state.get_board();
+        let bytes = to_snapshot(board).unwrap();
+        assert_eq!(*board, board_from_snapshot(&bytes).unwrap());
+    }
+
+    #[test]
+    fn test_old_snapshots_load() {
+        // A two player game saved with version 1, in its first turns after the setup.
+        let bytes: &[u8] = include_bytes!("../tests/fixtures/game_v1.snapshot");
+        assert_eq!([1, 0], bytes[4..6]);
+        let state = game_from_snapshot(bytes).unwrap();
+        assert_eq!(Phase::Trade, state.get_phase());
+        assert_eq!(5, state.get_turn());
+        assert_eq!(PlayerId(1), state.get_current_player());
+        assert_eq!(Some(6), state.get_last_roll());
+        assert_eq!(Hand([0, 0, 1, 3, 0]), state.get_player(PlayerId(0)).hand);
+        assert_eq!(Hand([0, 1, 0, 0, 0]), state.get_player(PlayerId(1)).hand);
+        assert_eq!(Hand([19, 18, 18, 16, 19]), *state.get_bank().get_cards());
+        assert_eq!(25, state.get_deck().len());
+
+        let board = state.get_board();
+        assert_eq!(vec![20, 35], board.get_buildings(PlayerId(0)));
+        assert_eq!(vec![21, 22], board.get_buildings(PlayerId(1)));
+        assert_eq!(vec![19, 84], board.get_roads(PlayerId(0)));
+        assert_eq!(vec![85, 86], board.get_roads(PlayerId(1)));
+        let settlement = Building { kind: BuildingKind::Settlement, owner: PlayerId(0) };
+        assert_eq!(Some(settlement), board.get_building(20));
+        for &player in &[PlayerId(0), PlayerId(1)] {
+            let player = state.get_player(player);
+            assert_eq!((3, 4, 13), (player.settlements, player.cities, player.roads));
+        }
+    }
+
+    #[test]
+    fn test_migrations() {
+        // A made up version 1 tree, and migrations that rename fields for versions 2 and 3.
+        fn rename(object: &mut Value, from: &str, to: &str) {
+            let object = object.as_object_mut().unwrap();
+            let value = object.remove(from).unwrap();
+            object.insert(to.to_string(), value);
+        }
+        fn rename_rules(value: &mut Value) {
+            rename(value, "rules", "settings");
+        }
+        fn rename_turns(value: &mut Value) {
+            rename(&mut value["settings"], "turns", "turn_limit");
+        }
+        let migrations: &[fn(&mut Value)] = &[rename_rules, rename_turns];
+        let current = json!({"settings": {"turn_limit": 4}});
+
+        let mut value = json!({"rules": {"turns": 4}});
+        migrate(&mut value, 1, migrations);
+        assert_eq!(current, value);
+        let mut value = json!({"settings": {"turns": 4}});
+        migrate(&mut value, 2, migrations);
+        assert_eq!(current, value);
+        let mut value = current.clone();
+        migrate(&mut value, 3, migrations);
+        assert_eq!(current, value);
+    }
+
+    #[test]
+    fn test_bad_snapshots() {
+        let bytes = to_snapshot(&make_game()).unwrap();
+        assert_eq!(Err(SnapshotError::NotASnapshot), decode(b"{}"));
+        assert_eq!(Err(SnapshotError::Truncated), decode(&bytes[..8]));
+        let mut flipped = bytes.clone();
+        flipped[100] ^= 1;
+        assert_eq!(Err(SnapshotError::BadChecksum), decode(&flipped));
+
+        let mut newer = bytes[..bytes.len() - 8].to_vec();
+        newer[4..6].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+        let sum = checksum(&newer);
+        newer.extend_from_slice(&sum.to_le_bytes());
+        assert_eq!(Err(SnapshotError::UnknownVersion(FORMAT_VERSION + 1)), decode(&newer));
+
+        let mut unknown = MAGIC.to_vec();
+        unknown.extend_from_slice(&[1, 0, 9]);
+        let sum = checksum(&unknown);
+        unknown.extend_from_slice(&sum.to_le_bytes());
+        assert_eq!(Err(SnapshotError::Corrupt("unknown tag")), decode(&unknown));
+
+        // A board that isn't a game is caught when loading the game.
+        let board = to_snapshot(make_game().get_board()).unwrap();
+        assert!(game_from_snapshot(&board).is_err());
+    }
+}
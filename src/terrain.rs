@@ -0,0 +1,187 @@
+// Terrains, the resources they produce, and the number tokens placed on them.
+
+use std::convert::TryFrom;
+use std::fmt;
+
+use shape::HexKind;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Resource {
+    Brick,
+    Lumber,
+    Ore,
+    Grain,
+    Wool,
+}
+
+pub const RESOURCES: [Resource; 5] = [
+    Resource::Brick,
+    Resource::Lumber,
+    Resource::Ore,
+    Resource::Grain,
+    Resource::Wool,
+];
+
+impl Resource {
+    // The position of the resource in RESOURCES, for indexing arrays of counts.
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+impl fmt::Display for Resource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Resource::Brick => "brick",
+            Resource::Lumber => "lumber",
+            Resource::Ore => "ore",
+            Resource::Grain => "grain",
+            Resource::Wool => "wool",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// The kinds of terrain.
+// Desert and Sea produce nothing.
+// GoldField produces one resource of the owner's choice.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TerrainKind {
+    Hills,
+    Forest,
+    Mountains,
+    Fields,
+    Pasture,
+    #[default]
+    Desert,
+    Sea,
+    GoldField,
+}
+
+impl TerrainKind {
+    // The resource produced by the terrain. Gold fields don't have a fixed resource.
+    pub fn resource(&self) -> Option<Resource> {
+        match *self {
+            TerrainKind::Hills => Some(Resource::Brick),
+            TerrainKind::Forest => Some(Resource::Lumber),
+            TerrainKind::Mountains => Some(Resource::Ore),
+            TerrainKind::Fields => Some(Resource::Grain),
+            TerrainKind::Pasture => Some(Resource::Wool),
+            TerrainKind::Desert | TerrainKind::Sea | TerrainKind::GoldField => None,
+        }
+    }
+
+    // The terrain that produces the resource.
+    pub fn from_resource(resource: Resource) -> TerrainKind {
+        match resource {
+            Resource::Brick => TerrainKind::Hills,
+            Resource::Lumber => TerrainKind::Forest,
+            Resource::Ore => TerrainKind::Mountains,
+            Resource::Grain => TerrainKind::Fields,
+            Resource::Wool => TerrainKind::Pasture,
+        }
+    }
+
+    // Whether the terrain produces anything when its number is rolled.
+    pub fn is_productive(&self) -> bool {
+        self.resource().is_some() || *self == TerrainKind::GoldField
+    }
+
+    pub fn get_hex_kind(&self) -> HexKind {
+        match *self {
+            TerrainKind::Sea => HexKind::Sea,
+            _ => HexKind::Land,
+        }
+    }
+}
+
+// A number token from 2 to 12. There is no 7 because a 7 moves the robber instead.
+// Saved tokens are checked like new ones.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "u8")]
+pub struct NumberToken(u8);
+
+impl NumberToken {
+    pub fn new(number: u8) -> Option<NumberToken> {
+        if (2..=12).contains(&number) && number != 7 {
+            Some(NumberToken(number))
+        } else {
+            None
+        }
+    }
+
+    pub fn get_number(&self) -> u8 {
+        self.0
+    }
+
+    // The number of dots printed on the token, which is the number of ways to roll it with two
+    // dice.
+    pub fn pips(&self) -> u32 {
+        6 - (7 - self.0 as i32).unsigned_abs()
+    }
+
+    // The chance of rolling the number with two dice.
+    pub fn probability(&self) -> f64 {
+        self.pips() as f64 / 36.0
+    }
+
+    // 6 and 8 are printed in red because they are rolled the most.
+    pub fn is_red(&self) -> bool {
+        self.0 == 6 || self.0 == 8
+    }
+}
+
+impl TryFrom<u8> for NumberToken {
+    type Error = String;
+
+    fn try_from(number: u8) -> Result<NumberToken, String> {
+        NumberToken::new(number).ok_or_else(|| format!("{} is not a number token", number))
+    }
+}
+
+impl fmt::Display for NumberToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resource_index() {
+        for (i, resource) in RESOURCES.iter().enumerate() {
+            assert_eq!(i, resource.index());
+        }
+    }
+
+    #[test]
+    fn test_terrain_resource() {
+        assert_eq!(Some(Resource::Brick), TerrainKind::Hills.resource());
+        assert_eq!(Some(Resource::Wool), TerrainKind::Pasture.resource());
+        assert_eq!(None, TerrainKind::Desert.resource());
+        assert_eq!(None, TerrainKind::GoldField.resource());
+        assert!(TerrainKind::GoldField.is_productive());
+        assert!(!TerrainKind::Sea.is_productive());
+        for resource in &RESOURCES {
+            assert_eq!(Some(*resource), TerrainKind::from_resource(*resource).resource());
+        }
+        assert_eq!(HexKind::Sea, TerrainKind::Sea.get_hex_kind());
+        assert_eq!(HexKind::Land, TerrainKind::Desert.get_hex_kind());
+    }
+
+    #[test]
+    fn test_number_token() {
+        assert_eq!(None, NumberToken::new(1));
+        assert_eq!(None, NumberToken::new(7));
+        assert_eq!(None, NumberToken::new(13));
+        let pips: Vec<u32> = [2, 3, 4, 5, 6, 8, 9, 10, 11, 12].iter()
+            .map(|&n| NumberToken::new(n).unwrap().pips())
+            .collect();
+        assert_eq!(vec![1, 2, 3, 4, 5, 5, 4, 3, 2, 1], pips);
+        assert!((NumberToken::new(6).unwrap().probability() - 5.0 / 36.0).abs() < 1e-12);
+        assert!(NumberToken::new(8).unwrap().is_red());
+        assert!(!NumberToken::new(9).unwrap().is_red());
+    }
+}
@@ -0,0 +1,304 @@
+// The hexes, intersections and edges of a board and how they fit together.
+//
+// Rules that only need the shape of the board can be written once against this trait and run
+// on either board model.
+// Hexes are named by their axial coordinates, which both models share.
+// Intersections and edges are named by indices that only mean something to the topology that
+// gave them out.
+// The corners and sides of a hex are listed clockwise from the north corner and the northeast
+// side, so the i-th side joins the i-th and (i + 1)-th corners.
+
+use board::Board;
+use coord::Axial;
+use triangle::{TriangleBoard, TriangleEdgeKind, VertexKind, VERTEX_DIRECTIONS};
+
+pub trait Topology {
+    fn hexes(&self) -> Vec<Axial>;
+    fn intersections(&self) -> Vec<usize>;
+    fn edges(&self) -> Vec<usize>;
+
+    fn hex_intersections(&self, hex: Axial) -> Option<[usize; 6]>;
+    fn hex_edges(&self, hex: Axial) -> Option<[usize; 6]>;
+    fn hex_neighbors(&self, hex: Axial) -> Vec<Axial>;
+
+    fn intersection_edges(&self, intersection: usize) -> Vec<usize>;
+    fn intersection_hexes(&self, intersection: usize) -> Vec<Axial>;
+    fn intersection_neighbors(&self, intersection: usize) -> Vec<usize>;
+
+    fn edge_endpoints(&self, edge: usize) -> Option<(usize, usize)>;
+    fn edge_hexes(&self, edge: usize) -> Vec<Axial>;
+
+    fn contains_hex(&self, hex: Axial) -> bool {
+        self.hex_intersections(hex).is_some()
+    }
+}
+
+fn to_axial(hexes: Vec<(usize, usize)>) -> Vec<Axial> {
+    hexes.into_iter().map(|(row, column)| Axial::from_hex(row, column)).collect()
+}
+
+impl Topology for Board {
+    fn hexes(&self) -> Vec<Axial> {
+        to_axial(self.get_hexes())
+    }
+
+    fn intersections(&self) -> Vec<usize> {
+        self.get_intersections()
+    }
+
+    fn edges(&self) -> Vec<usize> {
+        self.get_edges()
+    }
+
+    fn hex_intersections(&self, hex: Axial) -> Option<[usize; 6]> {
+        self.try_get_hex_intersections(hex.r, hex.q).ok()
+    }
+
+    fn hex_edges(&self, hex: Axial) -> Option<[usize; 6]> {
+        self.try_get_hex_edges(hex.r, hex.q).ok()
+    }
+
+    fn hex_neighbors(&self, hex: Axial) -> Vec<Axial> {
+        to_axial(self.get_hex_neighbors(hex.r, hex.q).unwrap_or_default())
+    }
+
+    fn intersection_edges(&self, intersection: usize) -> Vec<usize> {
+        self.try_get_intersection_edges(intersection).unwrap_or_default()
+    }
+
+    fn intersection_hexes(&self, intersection: usize) -> Vec<Axial> {
+        to_axial(self.try_get_intersection_hexes(intersection).unwrap_or_default())
+    }
+
+    fn intersection_neighbors(&self, intersection: usize) -> Vec<usize> {
+        self.try_get_intersection_neighbors(intersection).unwrap_or_default()
+    }
+
+    fn edge_endpoints(&self, edge: usize) -> Option<(usize, usize)> {
+        self.try_get_edge_endpoints(edge).ok()
+    }
+
+    fn edge_hexes(&self, edge: usize) -> Vec<Axial> {
+        to_axial(self.try_get_edge_hexes(edge).unwrap_or_default())
+    }
+}
+
+impl TriangleBoard {
+    fn get_face(&self, hex: Axial) -> Option<usize> {
+        self.get_vertex_from_hex(hex)
+            .filter(|&vertex| self.get_vertex_kind(vertex) == VertexKind::Face)
+    }
+
+    fn is_intersection(&self, vertex: usize) -> bool {
+        vertex < self.get_vertices_size() &&
+            self.get_vertex_kind(vertex) == VertexKind::Intersection
+    }
+
+    fn is_valid_edge(&self, edge: usize) -> bool {
+        edge < self.get_edges_size() && self.get_edge_kind(edge) == TriangleEdgeKind::Valid
+    }
+}
+
+impl Topology for TriangleBoard {
+    fn hexes(&self) -> Vec<Axial> {
+        (0..self.get_vertices_size())
+            .filter(|&vertex| self.get_vertex_kind(vertex) == VertexKind::Face)
+            .map(|vertex| self.get_hex_from_vertex(vertex))
+            .collect()
+    }
+
+    fn intersections(&self) -> Vec<usize> {
+        (0..self.get_vertices_size()).filter(|&vertex| self.is_intersection(vertex)).collect()
+    }
+
+    fn edges(&self) -> Vec<usize> {
+        (0..self.get_edges_size()).filter(|&edge| self.is_valid_edge(edge)).collect()
+    }
+
+    fn hex_intersections(&self, hex: Axial) -> Option<[usize; 6]> {
+        let face = self.get_face(hex)?;
+        let mut corners = [0; 6];
+        for (corner, direction) in corners.iter_mut().zip(VERTEX_DIRECTIONS.iter()) {
+            *corner = self.get_neighbor_vertex(face, *direction)?;
+        }
+        Some(corners)
+    }
+
+    fn hex_edges(&self, hex: Axial) -> Option<[usize; 6]> {
+        self.get_perimeter_edges(self.get_face(hex)?)
+    }
+
+    fn hex_neighbors(&self, hex: Axial) -> Vec<Axial> {
+        if self.get_face(hex).is_none() {
+            return vec![];
+        }
+        hex.neighbors()
+            .iter()
+            .cloned()
+            .filter(|&neighbor| self.get_face(neighbor).is_some())
+            .collect()
+    }
+
+    fn intersection_edges(&self, intersection: usize) -> Vec<usize> {
+        if !self.is_intersection(intersection) {
+            return vec![];
+        }
+        VERTEX_DIRECTIONS.iter()
+            .filter_map(|direction| self.get_neighbor_edge(intersection, *direction))
+            .filter(|&edge| self.is_valid_edge(edge))
+            .collect()
+    }
+
+    fn intersection_hexes(&self, intersection: usize) -> Vec<Axial> {
+        if !self.is_intersection(intersection) {
+            return vec![];
+        }
+        self.get_neighbor_vertices(intersection, VertexKind::Face)
+            .into_iter()
+            .map(|vertex| self.get_hex_from_vertex(vertex))
+            .collect()
+    }
+
+    fn intersection_neighbors(&self, intersection: usize) -> Vec<usize> {
+        self.intersection_edges(intersection)
+            .into_iter()
+            .map(|edge| {
+                let (a, b) = self.get_edge_vertices(edge);
+                if a == intersection { b } else { a }
+            })
+            .collect()
+    }
+
+    fn edge_endpoints(&self, edge: usize) -> Option<(usize, usize)> {
+        if self.is_valid_edge(edge) {
+            Some(self.get_edge_vertices(edge))
+        } else {
+            None
+        }
+    }
+
+    // The hexes on either side of an edge are the faces next to both of its ends.
+    fn edge_hexes(&self, edge: usize) -> Vec<Axial> {
+        let (a, b) = match self.edge_endpoints(edge) {
+            Some(endpoints) => endpoints,
+            None => return vec![],
+        };
+        let faces = self.get_neighbor_vertices(b, VertexKind::Face);
+        self.get_neighbor_vertices(a, VertexKind::Face)
+            .into_iter()
+            .filter(|face| faces.contains(face))
+            .map(|vertex| self.get_hex_from_vertex(vertex))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shape::Shape;
+    use std::collections::{BTreeSet, HashMap};
+    use std::fmt::Debug;
+    use std::hash::Hash;
+
+    // Map the intersections and edges of one topology onto the other through the corners and
+    // sides of the hexes, and check that the map is one to one.
+    fn match_up<A: Topology, B: Topology>(a: &A, b: &B)
+        -> (HashMap<usize, usize>, HashMap<usize, usize>) {
+        let mut intersections = HashMap::new();
+        let mut edges = HashMap::new();
+        for hex in a.hexes() {
+            let corners = (a.hex_intersections(hex).unwrap(), b.hex_intersections(hex).unwrap());
+            let sides = (a.hex_edges(hex).unwrap(), b.hex_edges(hex).unwrap());
+            for i in 0..6 {
+                let corner = *intersections.entry(corners.0[i]).or_insert(corners.1[i]);
+                assert_eq!(corners.1[i], corner);
+                assert_eq!(sides.1[i], *edges.entry(sides.0[i]).or_insert(sides.1[i]));
+            }
+        }
+        let distinct: BTreeSet<&usize> = intersections.values().collect();
+        assert_eq!(intersections.len(), distinct.len());
+        let distinct: BTreeSet<&usize> = edges.values().collect();
+        assert_eq!(edges.len(), distinct.len());
+        (intersections, edges)
+    }
+
+    fn set<T: Ord + Clone>(items: &[T]) -> BTreeSet<T> {
+        items.iter().cloned().collect()
+    }
+
+    fn mapped<T: Eq + Hash + Debug>(items: &[T], map: &HashMap<T, usize>) -> BTreeSet<usize> {
+        items.iter().map(|item| map[item]).collect()
+    }
+
+    fn assert_same_graph(shape: &Shape) {
+        let mut board: Board = Default::default();
+        board.init_shape(shape);
+        let triangle = TriangleBoard::from_shape(shape);
+
+        assert_eq!(set(&board.hexes()), set(&triangle.hexes()));
+        let (intersections, edges) = match_up(&board, &triangle);
+        assert_eq!(mapped(&board.intersections(), &intersections), set(&triangle.intersections()));
+        assert_eq!(mapped(&board.edges(), &edges), set(&triangle.edges()));
+
+        for hex in board.hexes() {
+            assert_eq!(set(&board.hex_neighbors(hex)), set(&triangle.hex_neighbors(hex)));
+        }
+        for intersection in board.intersections() {
+            let other = intersections[&intersection];
+            assert_eq!(mapped(&board.intersection_edges(intersection), &edges),
+                       set(&triangle.intersection_edges(other)));
+            assert_eq!(set(&board.intersection_hexes(intersection)),
+                       set(&triangle.intersection_hexes(other)));
+            assert_eq!(mapped(&board.intersection_neighbors(intersection), &intersections),
+                       set(&triangle.intersection_neighbors(other)));
+        }
+        for edge in board.edges() {
+            let other = edges[&edge];
+            let (a, b) = board.edge_endpoints(edge).unwrap();
+            assert_eq!(set(&[intersections[&a], intersections[&b]]),
+                       set(&[triangle.edge_endpoints(other).unwrap().0,
+                             triangle.edge_endpoints(other).unwrap().1]));
+            assert_eq!(set(&board.edge_hexes(edge)), set(&triangle.edge_hexes(other)));
+        }
+    }
+
+    #[test]
+    fn test_same_graph_for_rectangle() {
+        assert_same_graph(&Shape::rectangle(3, 4));
+    }
+
+    #[test]
+    fn test_same_graph_for_standard_board() {
+        assert_same_graph(&Shape::standard());
+    }
+
+    #[test]
+    fn test_same_graph_for_extended_board() {
+        assert_same_graph(&Shape::extended());
+    }
+
+    #[test]
+    fn test_same_graph_for_ring() {
+        assert_same_graph(&Shape::parse("
+            . L L
+             L . L
+              L L .
+        ").unwrap());
+    }
+
+    // Count the spots where a settlement could go on an empty board using only the trait.
+    fn count_corners<T: Topology>(topology: &T) -> usize {
+        topology.intersections().len()
+    }
+
+    #[test]
+    fn test_rules_run_on_either_board() {
+        let shape = Shape::standard();
+        let mut board: Board = Default::default();
+        board.init_shape(&shape);
+        let triangle = TriangleBoard::from_shape(&shape);
+        assert_eq!(count_corners(&board), count_corners(&triangle));
+        assert!(board.contains_hex(Axial::new(3, 3)));
+        assert!(!triangle.contains_hex(Axial::new(0, 0)));
+    }
+}
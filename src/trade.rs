@@ -0,0 +1,137 @@
+// Trades between players, and with the bank.
+//
+// The player whose turn it is offers a trade to everyone. The others accept it, reject it or
+// make a counteroffer, and the player whose turn it is then confirms the trade with one of the
+// players who accepted or countered. Offers are always written from the side of the player
+// whose turn it is, counteroffers included. Nobody can ask for cards that can't be given: an
+// offer has to ask for cards one of the others has, and a counteroffer for cards the player
+// whose turn it is has.
+// The player can also trade with the bank at their trade rates, in lots of the rate for one
+// card each. A trade can have several lots, like 8 brick for 2 ore at 4:1.
+
+use hand::Hand;
+use terrain::{Resource, RESOURCES};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Offer {
+    // The cards the player whose turn it is gives away.
+    pub give: Hand,
+    // The cards the player whose turn it is gets in return.
+    pub get: Hand,
+}
+
+impl Offer {
+    pub fn new(give: Hand, get: Hand) -> Offer {
+        Offer { give, get }
+    }
+
+    // An offer has to trade something for something, and can't have a resource on both sides.
+    pub fn is_valid(&self) -> bool {
+        !self.give.is_empty() && !self.get.is_empty() &&
+            RESOURCES.iter().all(|&resource| self.give.get(resource) == 0 ||
+                                 self.get.get(resource) == 0)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Response {
+    Accepted,
+    Rejected,
+    Countered(Offer),
+}
+
+// The offer on the table and what each player said to it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Negotiation {
+    pub offer: Offer,
+    // The responses, indexed by player.
+    pub responses: Vec<Option<Response>>,
+}
+
+impl Negotiation {
+    pub fn new(offer: Offer, players: usize) -> Negotiation {
+        Negotiation {
+            offer,
+            responses: vec![None; players],
+        }
+    }
+
+    // The offer the player agreed to, if any.
+    pub fn get_agreed(&self, player: usize) -> Option<Offer> {
+        match self.responses[player] {
+            Some(Response::Accepted) => Some(self.offer),
+            Some(Response::Countered(offer)) => Some(offer),
+            _ => None,
+        }
+    }
+}
+
+// The number of cards the bank gives for the cards at the rates, which are indexed by
+// Resource::index. Fails with the first resource not given in whole lots, and its rate.
+pub fn count_lots(give: &Hand, rates: &[u32; 5]) -> Result<u32, (Resource, u32)> {
+    let mut lots = 0;
+    for &resource in &RESOURCES {
+        let rate = rates[resource.index()];
+        if !give.get(resource).is_multiple_of(rate) {
+            return Err((resource, rate));
+        }
+        lots += give.get(resource) / rate;
+    }
+    Ok(lots)
+}
+
+// The most cards the hand can get from the bank at the rates, out of the cards the bank has.
+// A lot can't be traded for its own resource, so the lots of one resource can only take the
+// bank's other cards. Going by the cards the lots of each resource can reach, the most is the
+// smallest of all the lots, all the bank's cards, and for each resource, the lots of the other
+// resources plus the bank's cards of the other resources.
+pub fn get_lots(hand: &Hand, rates: &[u32; 5], bank: &Hand) -> u32 {
+    let lots: Vec<u32> = RESOURCES.iter()
+        .map(|&resource| hand.get(resource) / rates[resource.index()])
+        .collect();
+    let all_lots: u32 = lots.iter().sum();
+    let all_cards = bank.total();
+    RESOURCES.iter()
+        .map(|&resource| all_lots - lots[resource.index()] + all_cards - bank.get(resource))
+        .fold(all_lots.min(all_cards), u32::min)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use terrain::Resource;
+
+    #[test]
+    fn test_offer() {
+        let brick = Hand::single(Resource::Brick, 2);
+        let ore = Hand::single(Resource::Ore, 1);
+        assert!(Offer::new(brick, ore).is_valid());
+        assert!(!Offer::new(brick, Hand::default()).is_valid());
+        let mut both = ore;
+        both.add(Resource::Brick, 1);
+        assert!(!Offer::new(brick, both).is_valid());
+
+        let counter = Offer::new(Hand::single(Resource::Brick, 3), ore);
+        let mut negotiation = Negotiation::new(Offer::new(brick, ore), 3);
+        negotiation.responses[1] = Some(Response::Accepted);
+        negotiation.responses[2] = Some(Response::Countered(counter));
+        assert_eq!(None, negotiation.get_agreed(0));
+        assert_eq!(Some(Offer::new(brick, ore)), negotiation.get_agreed(1));
+        assert_eq!(Some(counter), negotiation.get_agreed(2));
+    }
+
+    #[test]
+    fn test_lots() {
+        let rates = [4, 4, 2, 3, 3];
+        assert_eq!(Ok(2), count_lots(&Hand::single(Resource::Brick, 8), &rates));
+        assert_eq!(Ok(3), count_lots(&Hand([4, 0, 2, 3, 0]), &rates));
+        assert_eq!(Err((Resource::Grain, 3)), count_lots(&Hand([4, 0, 0, 4, 0]), &rates));
+        let bank = Hand([19; 5]);
+        assert_eq!(4, get_lots(&Hand([9, 3, 3, 3, 0]), &rates, &bank));
+        // A nearly empty bank.
+        assert_eq!(2, get_lots(&Hand([9, 3, 3, 3, 0]), &rates, &Hand([0, 1, 0, 0, 1])));
+        assert_eq!(0, get_lots(&Hand([8, 0, 0, 0, 0]), &rates, &Hand([5, 0, 0, 0, 0])));
+        assert_eq!(1, get_lots(&Hand([8, 0, 0, 0, 0]), &rates, &Hand([5, 0, 0, 1, 0])));
+        assert_eq!(2, get_lots(&Hand([8, 0, 2, 0, 0]), &rates, &Hand([5, 0, 0, 1, 0])));
+    }
+}
@@ -0,0 +1,356 @@
+// Represent the game board as a grid of triangles.
+// This is the first board model of the game, kept so that rules written against the Topology
+// trait can be checked against two independent implementations.
+//
+// The grid of triangles is shaped as a rhombus.
+// The rhombus is orientated so that we get a horizontal (pointy topped) hexagonal grid.
+// The middle of each hex is a vertex of the grid, and so is each corner.
+// This is how the vertices are indexed.
+//
+// Indices for vertices
+//
+//        0
+//        |\
+//        | \ 1
+//        | /|\
+//      4 |/ | \ 2
+//        |\ | /|\
+//        |5\|/ | \ 3
+//        | /|\ | /|
+//      8 |/ |6\|/ |
+//         \ | /|\ |
+//          \|/ | \| 7
+//         9  \ | /|
+//             \|/ |
+//           10  \ |
+//                \|
+//              11
+//
+// There are three types of edges: vertical, right diagonal, and left diagonal.
+// Vertical means the edges going north to south.
+// Right diagonal means the edges going down from left to right.
+// Left diagonal means the edges going down from right to left.
+// The vertical edges are indexed by the vertex at their north end. The diagonal edges come
+// after them, indexed row by row in the same way.
+//
+// A vertex has six neighbors.
+//
+//                 North
+//                   |
+//    Northwest      |      Northeast
+//               \   |   /
+//                \  |  /
+//                   *
+//                /  |  \
+//               /   |   \
+//    Southwest      |      Southeast
+//                   |
+//                 South
+//
+// Moving north takes one row up, moving southeast takes one column right, and moving northeast
+// does both.
+
+use coord::Axial;
+use shape::{HexKind, Shape};
+
+// The kinds of vertices.
+// Invalid means they're not used.
+// Intersection means you can build settlements and cities on them.
+// Face means it is the middle of a hex.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VertexKind {
+    #[default]
+    Invalid,
+    Intersection,
+    Face,
+}
+
+// The kinds of edges.
+// Invalid means they're not used.
+// Valid means they are the side of a hex.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TriangleEdgeKind {
+    #[default]
+    Invalid,
+    Valid,
+}
+
+// The directions to the neighbors of a vertex, clockwise from the north.
+// The corners of a hex are in the same directions from its middle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VertexDirection {
+    North,
+    Northeast,
+    Southeast,
+    South,
+    Southwest,
+    Northwest,
+}
+
+pub const VERTEX_DIRECTIONS: [VertexDirection; 6] = [
+    VertexDirection::North,
+    VertexDirection::Northeast,
+    VertexDirection::Southeast,
+    VertexDirection::South,
+    VertexDirection::Southwest,
+    VertexDirection::Northwest,
+];
+
+impl VertexDirection {
+    // The change in (row, column) to get to the neighbor.
+    fn get_offset(&self) -> (isize, isize) {
+        match *self {
+            VertexDirection::North => (-1, 0),
+            VertexDirection::Northeast => (-1, 1),
+            VertexDirection::Southeast => (0, 1),
+            VertexDirection::South => (1, 0),
+            VertexDirection::Southwest => (1, -1),
+            VertexDirection::Northwest => (0, -1),
+        }
+    }
+}
+
+pub struct TriangleBoard {
+    width: usize,
+    height: usize,
+    vertical_edges_size: usize,
+    right_diagonal_edges_size: usize,
+    // The vertex in the middle of the hex at axial (0, 0).
+    origin: (usize, usize),
+    vertices: Vec<VertexKind>,
+    edges: Vec<TriangleEdgeKind>,
+}
+
+impl TriangleBoard {
+    // Make a triangular board with no hexes.
+    // The hex at axial (0, 0) has its middle at (1, 1), like in the original grid.
+    pub fn new(height: usize, width: usize) -> TriangleBoard {
+        assert!(0 < height);
+        assert!(0 < width);
+        let vertices_size = height * width;
+        let vertical_edges_size = (height - 1) * width;
+        let right_diagonal_edges_size = height * (width - 1);
+        let left_diagonal_edges_size = (height - 1) * (width - 1);
+        let edges_size = vertical_edges_size + right_diagonal_edges_size + left_diagonal_edges_size;
+        TriangleBoard {
+            width,
+            height,
+            vertical_edges_size,
+            right_diagonal_edges_size,
+            origin: (1, 1),
+            vertices: vec![VertexKind::Invalid; vertices_size],
+            edges: vec![TriangleEdgeKind::Invalid; edges_size],
+        }
+    }
+
+    // Make a board with a hex for every hex of the shape that is on the board.
+    // Sea hexes are hexes too; the triangle grid doesn't know about terrain.
+    pub fn from_shape(shape: &Shape) -> TriangleBoard {
+        let rows = shape.get_height();
+        let columns = shape.get_width();
+        // Moving east goes up a row, so the rows are shifted down to fit the first row.
+        let mut board = TriangleBoard::new(rows + columns + 1, 2 * columns + rows);
+        board.origin = (columns, 1);
+        for row in 0..rows {
+            for column in 0..columns {
+                if shape.get_hex_kind(row, column) != HexKind::OffBoard {
+                    let vertex = board.get_vertex_from_hex(Axial::from_hex(row, column)).unwrap();
+                    board.make_hexagon(vertex);
+                }
+            }
+        }
+        board
+    }
+
+    pub fn get_vertices_size(&self) -> usize {
+        self.vertices.len()
+    }
+
+    pub fn get_edges_size(&self) -> usize {
+        self.edges.len()
+    }
+
+    pub fn get_vertex_kind(&self, vertex: usize) -> VertexKind {
+        self.vertices[vertex]
+    }
+
+    pub fn get_edge_kind(&self, edge: usize) -> TriangleEdgeKind {
+        self.edges[edge]
+    }
+
+    fn get_vertex(&self, row: isize, column: isize) -> Option<usize> {
+        if row < 0 || column < 0 || row as usize >= self.height || column as usize >= self.width {
+            return None;
+        }
+        Some(row as usize * self.width + column as usize)
+    }
+
+    fn get_position(&self, vertex: usize) -> (isize, isize) {
+        ((vertex / self.width) as isize, (vertex % self.width) as isize)
+    }
+
+    // Get the vertex for the middle of the hex, if it fits on the grid.
+    pub fn get_vertex_from_hex(&self, hex: Axial) -> Option<usize> {
+        let row = self.origin.0 as isize - hex.q + hex.r;
+        let column = self.origin.1 as isize + 2 * hex.q + hex.r;
+        self.get_vertex(row, column)
+    }
+
+    pub fn get_hex_from_vertex(&self, vertex: usize) -> Axial {
+        let (row, column) = self.get_position(vertex);
+        let row = row - self.origin.0 as isize;
+        let column = column - self.origin.1 as isize;
+        // Solve row = r - q and column = 2q + r.
+        Axial::new((column - row) / 3, (column + 2 * row) / 3)
+    }
+
+    pub fn get_neighbor_vertex(&self, vertex: usize, direction: VertexDirection) -> Option<usize> {
+        let (row, column) = self.get_position(vertex);
+        let (row_offset, column_offset) = direction.get_offset();
+        self.get_vertex(row + row_offset, column + column_offset)
+    }
+
+    pub fn get_neighbor_edge(&self, vertex: usize, direction: VertexDirection) -> Option<usize> {
+        // There is no edge if there is no vertex at the other end.
+        self.get_neighbor_vertex(vertex, direction)?;
+        let (row, column) = self.get_position(vertex);
+        let width = self.width as isize;
+        let index = match direction {
+            VertexDirection::North => (row - 1) * width + column,
+            VertexDirection::South => row * width + column,
+            VertexDirection::Southeast =>
+                self.vertical_edges_size as isize + row * (width - 1) + column,
+            VertexDirection::Northwest =>
+                self.vertical_edges_size as isize + row * (width - 1) + column - 1,
+            VertexDirection::Northeast =>
+                (self.vertical_edges_size + self.right_diagonal_edges_size) as isize +
+                (row - 1) * (width - 1) + column,
+            VertexDirection::Southwest =>
+                (self.vertical_edges_size + self.right_diagonal_edges_size) as isize +
+                row * (width - 1) + column - 1,
+        };
+        Some(index as usize)
+    }
+
+    // Get the two vertices of the edge. The north or west end comes first.
+    pub fn get_edge_vertices(&self, edge: usize) -> (usize, usize) {
+        assert!(edge < self.edges.len());
+        let (row, column, direction) = if edge < self.vertical_edges_size {
+            (edge / self.width, edge % self.width, VertexDirection::South)
+        } else if edge < self.vertical_edges_size + self.right_diagonal_edges_size {
+            let offset = edge - self.vertical_edges_size;
+            (offset / (self.width - 1), offset % (self.width - 1), VertexDirection::Southeast)
+        } else {
+            let offset = edge - self.vertical_edges_size - self.right_diagonal_edges_size;
+            // Left diagonal edges are indexed by their south end.
+            (offset / (self.width - 1) + 1, offset % (self.width - 1), VertexDirection::Northeast)
+        };
+        let vertex = row * self.width + column;
+        (vertex, self.get_neighbor_vertex(vertex, direction).unwrap())
+    }
+
+    // Get the edges going around the hex with its middle at the vertex, clockwise from the
+    // northeast. Each joins two neighboring corners.
+    pub fn get_perimeter_edges(&self, vertex: usize) -> Option<[usize; 6]> {
+        let mut edges = [0; 6];
+        for i in 0..6 {
+            let corner = self.get_neighbor_vertex(vertex, VERTEX_DIRECTIONS[i])?;
+            // The next corner clockwise is two steps around from this corner.
+            edges[i] = self.get_neighbor_edge(corner, VERTEX_DIRECTIONS[(i + 2) % 6])?;
+        }
+        Some(edges)
+    }
+
+    // Make a hexagon centered on the given vertex.
+    pub fn make_hexagon(&mut self, vertex: usize) {
+        self.vertices[vertex] = VertexKind::Face;
+        for direction in &VERTEX_DIRECTIONS {
+            let corner = self.get_neighbor_vertex(vertex, *direction)
+                .expect("hexagon doesn't fit on the grid");
+            self.vertices[corner] = VertexKind::Intersection;
+        }
+        let perimeter = self.get_perimeter_edges(vertex).expect("hexagon doesn't fit on the grid");
+        for edge in &perimeter {
+            self.edges[*edge] = TriangleEdgeKind::Valid;
+        }
+    }
+
+    // Get the neighbors of the vertex of the given kind.
+    pub fn get_neighbor_vertices(&self, vertex: usize, kind: VertexKind) -> Vec<usize> {
+        VERTEX_DIRECTIONS.iter()
+            .filter_map(|direction| self.get_neighbor_vertex(vertex, *direction))
+            .filter(|&neighbor| self.vertices[neighbor] == kind)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The diagram at the top of the file has 3 rows of 4 vertices. This grid has a fourth row
+    // below them.
+    fn make_diagram_board() -> TriangleBoard {
+        TriangleBoard::new(4, 4)
+    }
+
+    #[test]
+    fn test_get_neighbor_vertex() {
+        let board = make_diagram_board();
+        let neighbors: Vec<Option<usize>> = VERTEX_DIRECTIONS.iter()
+            .map(|direction| board.get_neighbor_vertex(5, *direction))
+            .collect();
+        assert_eq!(vec![Some(1), Some(2), Some(6), Some(9), Some(8), Some(4)], neighbors);
+        assert_eq!(None, board.get_neighbor_vertex(0, VertexDirection::North));
+        assert_eq!(None, board.get_neighbor_vertex(3, VertexDirection::Southeast));
+        assert_eq!(None, board.get_neighbor_vertex(12, VertexDirection::Southwest));
+    }
+
+    #[test]
+    fn test_get_neighbor_edge() {
+        let board = make_diagram_board();
+        let edges: Vec<Option<usize>> = VERTEX_DIRECTIONS.iter()
+            .map(|direction| board.get_neighbor_edge(5, *direction))
+            .collect();
+        assert_eq!(vec![Some(1), Some(25), Some(16), Some(5), Some(27), Some(15)], edges);
+        assert_eq!(None, board.get_neighbor_edge(4, VertexDirection::Northwest));
+        for edge in 0..board.edges.len() {
+            let (a, b) = board.get_edge_vertices(edge);
+            let from_a: Vec<usize> = VERTEX_DIRECTIONS.iter()
+                .filter(|direction| board.get_neighbor_vertex(a, **direction) == Some(b))
+                .map(|direction| board.get_neighbor_edge(a, *direction).unwrap())
+                .collect();
+            assert_eq!(vec![edge], from_a);
+        }
+    }
+
+    #[test]
+    fn test_get_perimeter_edges() {
+        let board = make_diagram_board();
+        assert_eq!(Some([13, 2, 28, 18, 4, 24]), board.get_perimeter_edges(5));
+        assert_eq!(None, board.get_perimeter_edges(4));
+    }
+
+    #[test]
+    fn test_get_vertex_from_hex() {
+        let board = TriangleBoard::new(10, 10);
+        assert_eq!(Some(11), board.get_vertex_from_hex(Axial::new(0, 0)));
+        assert_eq!(Some(3), board.get_vertex_from_hex(Axial::new(1, 0)));
+        assert_eq!(Some(22), board.get_vertex_from_hex(Axial::new(0, 1)));
+        assert_eq!(Some(41), board.get_vertex_from_hex(Axial::new(-1, 2)));
+        assert_eq!(Some(25), board.get_vertex_from_hex(Axial::new(1, 2)));
+        assert_eq!(None, board.get_vertex_from_hex(Axial::new(-1, 0)));
+        assert_eq!(Axial::new(-1, 2), board.get_hex_from_vertex(41));
+    }
+
+    #[test]
+    fn test_make_hexagon() {
+        let mut board = make_diagram_board();
+        board.make_hexagon(5);
+        assert_eq!(VertexKind::Face, board.get_vertex_kind(5));
+        assert_eq!(vec![1, 2, 6, 9, 8, 4],
+                   board.get_neighbor_vertices(5, VertexKind::Intersection));
+        assert_eq!(TriangleEdgeKind::Valid, board.get_edge_kind(13));
+        assert_eq!(TriangleEdgeKind::Invalid, board.get_edge_kind(1));
+    }
+}